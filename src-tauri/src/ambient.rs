@@ -0,0 +1,494 @@
+//! Auto-brightness from an ambient light sensor
+//!
+//! Reads illuminance from a Linux IIO device under `/sys/bus/iio/devices`
+//! and maps it to a monitor brightness through a user-defined curve. A new
+//! brightness is only written once it differs from the last written value by
+//! the configured hysteresis, so sensor noise does not cause constant writes.
+//! A monitor can't follow both the sensor and circadian mode, since both
+//! would keep overwriting each other's brightness.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::circadian::CircadianConfig;
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::{SettingValue, SharedBackend};
+use crate::profiles::write_json;
+
+/// File name of the auto-brightness config inside the app config dir
+pub const AMBIENT_FILE: &str = "ambient.json";
+
+/// IIO devices directory relative to the sysfs root
+pub(crate) const IIO_DEVICES: &str = "bus/iio/devices";
+
+const STORE_VERSION: u32 = 1;
+
+/// One point of the lux to brightness curve
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurvePoint {
+    pub lux: f64,
+    pub brightness: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmbientConfig {
+    pub enabled: bool,
+    /// IIO device such as `iio:device0`, or `None` for the first light sensor
+    pub sensor: Option<String>,
+    /// Stable ids of the monitors to drive
+    pub monitor_ids: Vec<String>,
+    /// Points sorted by lux; brightness is interpolated between them
+    pub curve: Vec<CurvePoint>,
+    /// Minimum brightness change, in percent, before a new value is written
+    pub hysteresis: u8,
+    /// Seconds between sensor reads
+    pub poll_secs: u32,
+}
+
+impl Default for AmbientConfig {
+    fn default() -> Self {
+        let point = |lux, brightness| CurvePoint { lux, brightness };
+        Self {
+            enabled: false,
+            sensor: None,
+            monitor_ids: Vec::new(),
+            curve: vec![
+                point(0.0, 20),
+                point(50.0, 35),
+                point(300.0, 60),
+                point(1000.0, 85),
+                point(10000.0, 100),
+            ],
+            hysteresis: 5,
+            poll_secs: 2,
+        }
+    }
+}
+
+impl AmbientConfig {
+    pub fn validate(&self) -> Result<(), MsigdError> {
+        if self.curve.is_empty() {
+            return Err(MsigdError::InvalidValue(
+                "Brightness curve needs at least one point".to_string(),
+            ));
+        }
+        if self.curve.windows(2).any(|pair| pair[0].lux >= pair[1].lux) {
+            return Err(MsigdError::InvalidValue(
+                "Brightness curve points must be sorted by increasing lux".to_string(),
+            ));
+        }
+        for point in &self.curve {
+            if !point.lux.is_finite() || point.lux < 0.0 {
+                return Err(MsigdError::InvalidValue(
+                    "Curve lux values must be 0 or more".to_string(),
+                ));
+            }
+            SettingValue::Brightness(point.brightness).to_msigd()?;
+        }
+        if self.hysteresis > 100 {
+            return Err(MsigdError::InvalidValue(
+                "Hysteresis must be 0-100".to_string(),
+            ));
+        }
+        if self.poll_secs == 0 {
+            return Err(MsigdError::InvalidValue(
+                "Poll interval must be at least 1 second".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail if an enabled circadian config drives one of the same monitors
+    pub fn check_conflicts(&self, circadian: &CircadianConfig) -> Result<(), MsigdError> {
+        if !self.enabled || !circadian.enabled {
+            return Ok(());
+        }
+        match self.monitor_ids.iter().find(|id| circadian.monitor_ids.contains(id)) {
+            Some(id) => Err(MsigdError::InvalidValue(format!(
+                "Monitor {} can't follow both auto-brightness and circadian mode",
+                id
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Brightness for an illuminance, clamped to the ends of the curve
+    pub fn brightness_for(&self, lux: f64) -> u8 {
+        let (Some(first), Some(last)) = (self.curve.first(), self.curve.last()) else {
+            return 0;
+        };
+        if lux <= first.lux {
+            return first.brightness;
+        }
+        self.curve
+            .windows(2)
+            .find(|pair| lux <= pair[1].lux)
+            .map(|pair| {
+                let (low, high) = (pair[0], pair[1]);
+                let t = (lux - low.lux) / (high.lux - low.lux);
+                let (low_b, high_b) = (f64::from(low.brightness), f64::from(high.brightness));
+                (low_b + (high_b - low_b) * t).round() as u8
+            })
+            .unwrap_or(last.brightness)
+    }
+
+    /// Whether `target` is worth writing over the last written brightness
+    fn should_write(&self, last: Option<u8>, target: u8) -> bool {
+        let Some(last) = last else {
+            return true;
+        };
+        let change = last.abs_diff(target);
+        // Always let the value settle at the ends of the curve
+        let at_end = [self.curve.first(), self.curve.last()]
+            .into_iter()
+            .flatten()
+            .any(|point| point.brightness == target);
+        change > 0 && (change >= self.hysteresis || at_end)
+    }
+}
+
+/// An IIO device with an illuminance channel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LightSensor {
+    /// Directory name under `/sys/bus/iio/devices`, e.g. `iio:device0`
+    pub device: String,
+    /// Driver-reported name, e.g. `als` or `acpi-als`
+    pub name: String,
+}
+
+fn has_illuminance(dir: &Path) -> bool {
+    dir.join("in_illuminance_input").exists() || dir.join("in_illuminance_raw").exists()
+}
+
+/// Light sensors under `sysfs_root`, sorted by device
+pub fn light_sensors(sysfs_root: &Path) -> Vec<LightSensor> {
+    let Ok(entries) = fs::read_dir(sysfs_root.join(IIO_DEVICES)) else {
+        return Vec::new();
+    };
+    let mut sensors: Vec<LightSensor> = entries
+        .flatten()
+        .filter(|entry| has_illuminance(&entry.path()))
+        .map(|entry| LightSensor {
+            device: entry.file_name().to_string_lossy().into_owned(),
+            name: fs::read_to_string(entry.path().join("name"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_default(),
+        })
+        .collect();
+    sensors.sort_by(|a, b| a.device.cmp(&b.device));
+    sensors
+}
+
+fn read_number(path: &Path) -> Result<Option<f64>, MsigdError> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    text.trim().parse().map(Some).map_err(|_| {
+        MsigdError::SensorError(format!("Unreadable value in {}", path.display()))
+    })
+}
+
+/// Current illuminance in lux reported by an IIO device
+///
+/// Uses the processed `in_illuminance_input` channel when the driver has one,
+/// otherwise `(in_illuminance_raw + offset) * scale`.
+pub fn read_lux(sysfs_root: &Path, device: &str) -> Result<f64, MsigdError> {
+    let dir = sysfs_root.join(IIO_DEVICES).join(device);
+    if let Some(lux) = read_number(&dir.join("in_illuminance_input"))? {
+        return Ok(lux);
+    }
+    let raw = read_number(&dir.join("in_illuminance_raw"))?.ok_or_else(|| {
+        MsigdError::SensorError(format!("{} has no illuminance channel", device))
+    })?;
+    let offset = read_number(&dir.join("in_illuminance_offset"))?.unwrap_or(0.0);
+    let scale = read_number(&dir.join("in_illuminance_scale"))?.unwrap_or(1.0);
+    Ok(((raw + offset) * scale).max(0.0))
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    config: AmbientConfig,
+}
+
+/// Auto-brightness config kept in memory and mirrored to a JSON file
+pub struct AmbientStore {
+    path: PathBuf,
+    config: Mutex<AmbientConfig>,
+}
+
+impl AmbientStore {
+    /// Load the config from `path`, starting disabled if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let config = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.config)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn get(&self) -> AmbientConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Save the config, keeping the current one if it can't be written
+    pub fn set(&self, config: AmbientConfig) -> Result<(), MsigdError> {
+        config.validate()?;
+        let mut current = self.config.lock().unwrap();
+        let file = StoreFile {
+            version: STORE_VERSION,
+            config,
+        };
+        write_json(&self.path, &file)?;
+        *current = file.config;
+        Ok(())
+    }
+}
+
+/// Writes brightness from sensor readings, skipping changes below the hysteresis
+pub struct AutoBrightness {
+    sysfs_root: PathBuf,
+    /// Last brightness written per monitor
+    applied: HashMap<String, u8>,
+}
+
+impl AutoBrightness {
+    pub fn new(sysfs_root: PathBuf) -> Self {
+        Self {
+            sysfs_root,
+            applied: HashMap::new(),
+        }
+    }
+
+    /// Read the sensor and move every configured monitor along the curve
+    ///
+    /// Fails when the sensor can't be read; otherwise returns the monitors
+    /// that failed, which are rewritten on the next step.
+    pub async fn step(
+        &mut self,
+        backend: &dyn MonitorBackend,
+        config: &AmbientConfig,
+    ) -> Result<Vec<(String, MsigdError)>, MsigdError> {
+        if !config.enabled {
+            self.applied.clear();
+            return Ok(Vec::new());
+        }
+        let device = match &config.sensor {
+            Some(device) => device.clone(),
+            None => light_sensors(&self.sysfs_root)
+                .into_iter()
+                .next()
+                .map(|sensor| sensor.device)
+                .ok_or_else(|| {
+                    MsigdError::SensorError("No ambient light sensor found".to_string())
+                })?,
+        };
+        let target = config.brightness_for(read_lux(&self.sysfs_root, &device)?);
+
+        let mut errors = Vec::new();
+        for monitor_id in &config.monitor_ids {
+            if !config.should_write(self.applied.get(monitor_id).copied(), target) {
+                continue;
+            }
+            match write_brightness(backend, monitor_id, target).await {
+                Ok(()) => {
+                    self.applied.insert(monitor_id.clone(), target);
+                }
+                Err(e) => {
+                    self.applied.remove(monitor_id);
+                    errors.push((monitor_id.clone(), e));
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
+
+async fn write_brightness(
+    backend: &dyn MonitorBackend,
+    monitor_id: &str,
+    brightness: u8,
+) -> Result<(), MsigdError> {
+    let (def, value) = SettingValue::Brightness(brightness).to_msigd()?;
+    backend
+        .set(monitor_id, def.flag, &value)
+        .await
+        .map_err(|e| e.with_setting(def.flag).with_monitor(monitor_id))?;
+    Ok(())
+}
+
+/// Drive brightness from the ambient light sensor in the background
+pub fn spawn(backend: SharedBackend, store: Arc<AmbientStore>) {
+    tauri::async_runtime::spawn(async move {
+        let mut auto = AutoBrightness::new(PathBuf::from("/sys"));
+        // Only log a sensor error once until it changes, polling is frequent
+        let mut sensor_error = None;
+        loop {
+            let config = store.get();
+            match auto.step(backend.as_ref(), &config).await {
+                Ok(errors) => {
+                    sensor_error = None;
+                    for (monitor_id, e) in errors {
+                        eprintln!("Auto-brightness update failed on {}: {}", monitor_id, e);
+                    }
+                }
+                Err(e) => {
+                    let message = e.to_string();
+                    if sensor_error.as_ref() != Some(&message) {
+                        eprintln!("Auto-brightness paused: {}", message);
+                        sensor_error = Some(message);
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(config.poll_secs.max(1).into())).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::mock::MockBackend;
+    use crate::test_support::{FakeSysfs, TempDir};
+
+    fn config() -> AmbientConfig {
+        AmbientConfig {
+            enabled: true,
+            monitor_ids: vec!["1".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_curve_interpolates_and_clamps() {
+        let config = config();
+        assert_eq!(config.brightness_for(0.0), 20);
+        assert_eq!(config.brightness_for(175.0), 48);
+        assert_eq!(config.brightness_for(300.0), 60);
+        assert_eq!(config.brightness_for(50000.0), 100);
+    }
+
+    #[test]
+    fn test_sensors_and_lux_from_sysfs() {
+        let sysfs = FakeSysfs::new("iio-sensors");
+        sysfs.write_iio("iio:device0", "name", "accel_3d");
+        sysfs.write_iio("iio:device0", "in_accel_x_raw", "12");
+        sysfs.write_iio("iio:device1", "name", "als");
+        sysfs.write_iio("iio:device1", "in_illuminance_raw", "400");
+        sysfs.write_iio("iio:device1", "in_illuminance_offset", "-100");
+        sysfs.write_iio("iio:device1", "in_illuminance_scale", "0.5");
+        sysfs.write_iio("iio:device2", "in_illuminance_input", "812.5");
+
+        let devices: Vec<String> =
+            light_sensors(sysfs.root()).into_iter().map(|s| s.device).collect();
+        assert_eq!(devices, ["iio:device1", "iio:device2"]);
+        assert_eq!(light_sensors(sysfs.root())[0].name, "als");
+
+        assert_eq!(read_lux(sysfs.root(), "iio:device1").unwrap(), 150.0);
+        assert_eq!(read_lux(sysfs.root(), "iio:device2").unwrap(), 812.5);
+        let err = read_lux(sysfs.root(), "iio:device0").unwrap_err();
+        assert_eq!(err.code(), "sensor_error");
+    }
+
+    #[tokio::test]
+    async fn test_step_ignores_small_fluctuations() {
+        let sysfs = FakeSysfs::new("iio-step");
+        sysfs.write_iio("iio:device0", "in_illuminance_raw", "300");
+        let backend = MockBackend::default();
+        let mut auto = AutoBrightness::new(sysfs.root().to_path_buf());
+        let config = config();
+        let brightness = || backend.monitor("1").unwrap().settings.brightness;
+
+        assert!(auto.step(&backend, &config).await.unwrap().is_empty());
+        assert_eq!(brightness(), 60);
+
+        // 60 -> 63 stays within the hysteresis
+        sysfs.write_iio("iio:device0", "in_illuminance_raw", "380");
+        auto.step(&backend, &config).await.unwrap();
+        assert_eq!(backend.writes().len(), 1);
+
+        sysfs.write_iio("iio:device0", "in_illuminance_raw", "1000");
+        auto.step(&backend, &config).await.unwrap();
+        assert_eq!(brightness(), 85);
+
+        // Small moves still reach the end of the curve
+        sysfs.write_iio("iio:device0", "in_illuminance_raw", "9500");
+        auto.step(&backend, &config).await.unwrap();
+        assert_eq!(brightness(), 99);
+        sysfs.write_iio("iio:device0", "in_illuminance_raw", "20000");
+        auto.step(&backend, &config).await.unwrap();
+        auto.step(&backend, &config).await.unwrap();
+        assert_eq!(brightness(), 100);
+        assert_eq!(backend.writes().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_step_fails_without_sensor() {
+        let sysfs = FakeSysfs::new("iio-missing");
+        let backend = MockBackend::default();
+        let mut auto = AutoBrightness::new(sysfs.root().to_path_buf());
+
+        let err = auto.step(&backend, &config()).await.unwrap_err();
+        assert_eq!(err.code(), "sensor_error");
+        assert!(backend.writes().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_invalid_config() {
+        let mut unsorted = config();
+        unsorted.curve.swap(0, 1);
+        assert!(unsorted.validate().is_err());
+
+        let mut too_bright = config();
+        too_bright.curve[4].brightness = 120;
+        assert!(too_bright.validate().is_err());
+        assert!(config().validate().is_ok());
+    }
+
+    #[test]
+    fn test_store_keeps_config_it_cannot_save() {
+        let dir = TempDir::new("ambient");
+        let store = AmbientStore::load(dir.join(AMBIENT_FILE));
+        store.set(config()).unwrap();
+        assert!(AmbientStore::load(dir.join(AMBIENT_FILE)).get().enabled);
+
+        dir.write("file", "");
+        let unsaved = AmbientStore::load(dir.join("file").join(AMBIENT_FILE));
+        assert!(unsaved.set(config()).is_err());
+        assert!(!unsaved.get().enabled);
+    }
+
+    #[test]
+    fn test_rejects_monitors_driven_by_circadian_mode() {
+        let mut circadian = CircadianConfig {
+            enabled: true,
+            monitor_ids: vec!["2".to_string(), "1".to_string()],
+            ..Default::default()
+        };
+        let err = config().check_conflicts(&circadian).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid value: Monitor 1 can't follow both auto-brightness and circadian mode"
+        );
+
+        circadian.monitor_ids = vec!["2".to_string()];
+        assert!(config().check_conflicts(&circadian).is_ok());
+        circadian.monitor_ids = vec!["1".to_string()];
+        circadian.enabled = false;
+        assert!(config().check_conflicts(&circadian).is_ok());
+    }
+}
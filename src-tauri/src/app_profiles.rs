@@ -0,0 +1,624 @@
+//! Per-application profile switching
+//!
+//! Scans `/proc` for running processes and applies a profile to a monitor
+//! while a matching process runs. When several rules match the same monitor
+//! the highest priority wins. The settings a profile changes are read from
+//! the monitor before the first switch and written back once no rule matches
+//! anymore. Mystic Light can't be read from the monitor, so the last config
+//! the app applied before the switch is written back instead, if there is one.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::batch::SettingsBatch;
+use crate::msigd::identity::StableIdLookup;
+use crate::msigd::{
+    FieldResult, MsigdParser, MysticLightConfig, PartialMonitorSettings, SharedBackend,
+};
+use crate::mystic_light::MysticLightStore;
+use crate::profiles::{apply_mystic_light, write_json, ProfileStore};
+
+/// File name of the app rule store inside the app config dir
+pub const APP_RULES_FILE: &str = "app_rules.json";
+
+/// Event emitted when a monitor switches profile for an app or restores
+pub const APP_PROFILE_SWITCHED: &str = "app-profile-switched";
+
+/// How often `/proc` is scanned
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+const STORE_VERSION: u32 = 1;
+
+/// What part of a process a rule's pattern is matched against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchOn {
+    /// Executable or process name, ignoring case
+    #[default]
+    Name,
+    /// Substring of the full command line, ignoring case; needed for games
+    /// run through Wine or Proton
+    Cmdline,
+}
+
+/// Apply a profile to a monitor while a process runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppRule {
+    pub name: String,
+    pub process: String,
+    #[serde(default)]
+    pub match_on: MatchOn,
+    pub profile: String,
+    pub monitor_id: String,
+    /// Higher wins when several rules match the same monitor
+    #[serde(default)]
+    pub priority: i32,
+    pub enabled: bool,
+}
+
+impl AppRule {
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let pattern = self.process.to_lowercase();
+        match self.match_on {
+            MatchOn::Name => [Some(&process.name), process.exe.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|name| name.to_lowercase() == pattern),
+            MatchOn::Cmdline => process.cmdline.to_lowercase().contains(&pattern),
+        }
+    }
+}
+
+/// A running process as seen in `/proc`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Kernel process name from `comm`, at most 15 characters
+    pub name: String,
+    /// File name of the executable, from `exe` or the first argument
+    pub exe: Option<String>,
+    /// Arguments joined by spaces
+    pub cmdline: String,
+}
+
+fn file_name(path: &str) -> Option<String> {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Processes under `proc_root`, skipping any that exit while being read
+pub fn scan_processes(proc_root: &Path) -> Vec<ProcessInfo> {
+    let Ok(entries) = fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+    let mut processes: Vec<ProcessInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+            let name = fs::read_to_string(dir.join("comm")).ok()?.trim_end().to_string();
+            let args = fs::read(dir.join("cmdline")).unwrap_or_default();
+            let args: Vec<String> = args
+                .split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+            let exe = fs::read_link(dir.join("exe"))
+                .ok()
+                .and_then(|path| file_name(&path.to_string_lossy()))
+                .or_else(|| args.first().and_then(|arg| file_name(arg)));
+            Some(ProcessInfo {
+                pid,
+                name,
+                exe,
+                cmdline: args.join(" "),
+            })
+        })
+        .collect();
+    processes.sort_by_key(|process| process.pid);
+    processes
+}
+
+/// Result of switching a monitor's profile, or restoring it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSwitch {
+    pub monitor_id: String,
+    /// Rule now in effect, `None` after restoring
+    pub rule: Option<String>,
+    pub profile: Option<String>,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub results: Vec<FieldResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    rules: Vec<AppRule>,
+}
+
+/// App rules kept in memory and mirrored to a JSON file
+pub struct AppRuleStore {
+    path: PathBuf,
+    rules: Mutex<Vec<AppRule>>,
+}
+
+impl AppRuleStore {
+    /// Load rules from `path`, starting empty if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let rules = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.rules)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            rules: Mutex::new(rules),
+        }
+    }
+
+    pub fn list(&self) -> Vec<AppRule> {
+        self.rules.lock().unwrap().clone()
+    }
+
+    /// Insert a rule, replacing any existing one with the same name
+    pub fn save(&self, rule: AppRule) -> Result<(), MsigdError> {
+        if rule.name.trim().is_empty() {
+            return Err(MsigdError::InvalidValue(
+                "Rule name must not be empty".to_string(),
+            ));
+        }
+        if rule.process.trim().is_empty() {
+            return Err(MsigdError::InvalidValue(
+                "Process to match must not be empty".to_string(),
+            ));
+        }
+        let mut rules = self.rules.lock().unwrap();
+        match rules.iter_mut().find(|r| r.name == rule.name) {
+            Some(existing) => *existing = rule,
+            None => rules.push(rule),
+        }
+        self.persist(&rules)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), MsigdError> {
+        let mut rules = self.rules.lock().unwrap();
+        let len = rules.len();
+        rules.retain(|r| r.name != name);
+        if rules.len() == len {
+            return Err(MsigdError::RuleNotFound(name.to_string()));
+        }
+        self.persist(&rules)
+    }
+
+    fn persist(&self, rules: &[AppRule]) -> Result<(), MsigdError> {
+        let file = StoreFile {
+            version: STORE_VERSION,
+            rules: rules.to_vec(),
+        };
+        write_json(&self.path, &file)
+    }
+}
+
+/// A monitor currently switched by a rule
+struct Active {
+    rule: String,
+    profile: String,
+    /// Reported settings from before the first switch, by field
+    snapshot: Option<Map<String, Value>>,
+    /// Fields written by any profile applied since then
+    touched: BTreeSet<String>,
+    /// Last known Mystic Light config from before the first switch
+    mystic_light: Option<MysticLightConfig>,
+    /// Whether any profile applied since then set Mystic Light
+    mystic_light_touched: bool,
+}
+
+/// Switches monitors between app profiles as processes start and exit
+pub struct AppWatcher {
+    proc_root: PathBuf,
+    mystic_light: Arc<MysticLightStore>,
+    ids: StableIdLookup,
+    active: HashMap<String, Active>,
+}
+
+impl AppWatcher {
+    pub fn new(proc_root: PathBuf, mystic_light: Arc<MysticLightStore>) -> Self {
+        Self {
+            proc_root,
+            mystic_light,
+            ids: StableIdLookup::default(),
+            active: HashMap::new(),
+        }
+    }
+
+    /// Scan processes and switch or restore monitors whose winning rule changed
+    pub async fn poll(
+        &mut self,
+        backend: &dyn MonitorBackend,
+        rules: &[AppRule],
+        profiles: &ProfileStore,
+    ) -> Vec<AppSwitch> {
+        let processes = scan_processes(&self.proc_root);
+        let mut winners: HashMap<&str, &AppRule> = HashMap::new();
+        for rule in rules.iter().filter(|r| r.enabled) {
+            if !processes.iter().any(|process| rule.matches(process)) {
+                continue;
+            }
+            // Earlier rules win ties
+            let current = winners.get(rule.monitor_id.as_str());
+            if current.map_or(true, |current| rule.priority > current.priority) {
+                winners.insert(&rule.monitor_id, rule);
+            }
+        }
+
+        let mut switches = Vec::new();
+        let ended: Vec<String> = self
+            .active
+            .keys()
+            .filter(|monitor_id| !winners.contains_key(monitor_id.as_str()))
+            .cloned()
+            .collect();
+        for monitor_id in ended {
+            if let Some(active) = self.active.remove(&monitor_id) {
+                switches.push(restore(backend, &monitor_id, active).await);
+            }
+        }
+
+        let mut winners: Vec<(&str, &AppRule)> = winners.into_iter().collect();
+        winners.sort_by_key(|(monitor_id, _)| *monitor_id);
+        for (monitor_id, rule) in winners {
+            let unchanged = self
+                .active
+                .get(monitor_id)
+                .is_some_and(|a| a.rule == rule.name && a.profile == rule.profile);
+            if !unchanged {
+                switches.push(self.switch(backend, rule, profiles).await);
+            }
+        }
+        switches
+    }
+
+    async fn switch(
+        &mut self,
+        backend: &dyn MonitorBackend,
+        rule: &AppRule,
+        profiles: &ProfileStore,
+    ) -> AppSwitch {
+        let mut results = Vec::new();
+        if !self.active.contains_key(&rule.monitor_id) {
+            let snapshot = match snapshot(backend, &rule.monitor_id).await {
+                Ok(snapshot) => Some(snapshot),
+                Err(e) => {
+                    results.push(FieldResult::failed("restore", &e));
+                    None
+                }
+            };
+            // The store is keyed by stable id
+            let mystic_light = match self.ids.resolve(backend, &rule.monitor_id).await {
+                Some(id) => self.mystic_light.get(&id).map(|last| last.config),
+                None => None,
+            };
+            let active = Active {
+                rule: String::new(),
+                profile: String::new(),
+                snapshot,
+                touched: BTreeSet::new(),
+                mystic_light,
+                mystic_light_touched: false,
+            };
+            self.active.insert(rule.monitor_id.clone(), active);
+        }
+
+        let active = self.active.get_mut(&rule.monitor_id).unwrap();
+        active.rule = rule.name.clone();
+        active.profile = rule.profile.clone();
+        match profiles.get(&rule.profile) {
+            Ok(profile) => {
+                if let Ok(Value::Object(fields)) = serde_json::to_value(&profile.settings) {
+                    active.touched.extend(
+                        fields
+                            .into_iter()
+                            .filter(|(_, value)| !value.is_null())
+                            .map(|(field, _)| field),
+                    );
+                }
+                active.mystic_light_touched |= profile.mystic_light.is_some();
+                results.extend(profile.apply(backend, &rule.monitor_id).await);
+            }
+            Err(e) => results.push(FieldResult::failed("profile", &e)),
+        }
+
+        AppSwitch {
+            monitor_id: rule.monitor_id.clone(),
+            rule: Some(rule.name.clone()),
+            profile: Some(rule.profile.clone()),
+            timestamp: timestamp(),
+            results,
+        }
+    }
+}
+
+/// Settings the monitor reports right now, by field
+async fn snapshot(
+    backend: &dyn MonitorBackend,
+    monitor_id: &str,
+) -> Result<Map<String, Value>, MsigdError> {
+    let output = backend.query(monitor_id).await?;
+    let queried = MsigdParser::parse_query(&output)?;
+    let mut fields = match serde_json::to_value(&queried.settings) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    fields.retain(|field, _| queried.reported.contains(field));
+    Ok(fields)
+}
+
+/// Write back the snapshot values of every field the app profiles changed
+async fn restore(backend: &dyn MonitorBackend, monitor_id: &str, active: Active) -> AppSwitch {
+    let mut results = match active.snapshot {
+        Some(mut snapshot) => {
+            snapshot.retain(|field, _| active.touched.contains(field));
+            match serde_json::from_value::<PartialMonitorSettings>(Value::Object(snapshot)) {
+                Ok(settings) => {
+                    SettingsBatch::from_partial(&settings)
+                        .apply(backend, monitor_id)
+                        .await
+                }
+                Err(e) => vec![FieldResult::failed(
+                    "restore",
+                    &MsigdError::ParseError(e.to_string()),
+                )],
+            }
+        }
+        None => Vec::new(),
+    };
+    if let (true, Some(config)) = (active.mystic_light_touched, &active.mystic_light) {
+        results.push(apply_mystic_light(backend, monitor_id, config).await);
+    }
+
+    AppSwitch {
+        monitor_id: monitor_id.to_string(),
+        rule: None,
+        profile: None,
+        timestamp: timestamp(),
+        results,
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Watch running processes in the background for as long as the app runs
+pub fn spawn(app: AppHandle, backend: SharedBackend, store: Arc<AppRuleStore>) {
+    tauri::async_runtime::spawn(async move {
+        let mystic_light = app.state::<Arc<MysticLightStore>>().inner().clone();
+        let mut watcher = AppWatcher::new(PathBuf::from("/proc"), mystic_light);
+        loop {
+            let profiles = app.state::<ProfileStore>();
+            let switches = watcher
+                .poll(backend.as_ref(), &store.list(), &profiles)
+                .await;
+            for switch in switches {
+                log_switch(&switch);
+                let _ = app.emit(APP_PROFILE_SWITCHED, switch);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+fn log_switch(switch: &AppSwitch) {
+    let action = switch.rule.as_deref().unwrap_or("restore");
+    for result in &switch.results {
+        if let Some(e) = &result.error {
+            eprintln!(
+                "App profile {} failed to set {} on {}: {}",
+                action, result.field, switch.monitor_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::mock::MockBackend;
+    use crate::msigd::mystic::MysticLightMode;
+    use crate::msigd::GameMode;
+    use crate::mystic_light::{MysticLightRecorder, MYSTIC_LIGHT_FILE};
+    use crate::profiles::Profile;
+    use crate::test_support::TempDir;
+
+    /// Fake `/proc` with one directory per running process
+    struct FakeProc(TempDir);
+
+    impl FakeProc {
+        fn new(name: &str) -> Self {
+            Self(TempDir::new(name))
+        }
+
+        fn root(&self) -> PathBuf {
+            self.0.path().to_path_buf()
+        }
+
+        fn start(&self, pid: u32, comm: &str, args: &[&str]) {
+            let dir = PathBuf::from(pid.to_string());
+            self.0.write(dir.join("comm"), format!("{}\n", comm));
+            let cmdline: Vec<u8> =
+                args.iter().flat_map(|arg| [arg.as_bytes(), b"\0"].concat()).collect();
+            self.0.write(dir.join("cmdline"), cmdline);
+        }
+
+        fn exit(&self, pid: u32) {
+            self.0.remove(pid.to_string());
+        }
+    }
+
+    fn rule(name: &str, process: &str, match_on: MatchOn, profile: &str, priority: i32) -> AppRule {
+        AppRule {
+            name: name.to_string(),
+            process: process.to_string(),
+            match_on,
+            profile: profile.to_string(),
+            monitor_id: "1".to_string(),
+            priority,
+            enabled: true,
+        }
+    }
+
+    fn watcher(proc: &FakeProc, dir: &TempDir) -> AppWatcher {
+        let mystic_light = MysticLightStore::load(dir.join(MYSTIC_LIGHT_FILE));
+        AppWatcher::new(proc.root(), Arc::new(mystic_light))
+    }
+
+    fn profiles(dir: &TempDir) -> ProfileStore {
+        let store = ProfileStore::load(dir.join("profiles.json"));
+        let fps = PartialMonitorSettings {
+            game_mode: Some(GameMode::Fps),
+            zero_latency: Some(true),
+            ..Default::default()
+        };
+        let bright = PartialMonitorSettings {
+            brightness: Some(100),
+            ..Default::default()
+        };
+        for (name, settings) in [("FPS", fps), ("Bright", bright)] {
+            let profile = Profile {
+                name: name.to_string(),
+                settings,
+                mystic_light: None,
+            };
+            store.save(profile).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_scan_and_match_processes() {
+        let proc = FakeProc::new("proc-scan");
+        proc.start(42, "wine64-preloader", &["C:\\Games\\Doom.exe", "-fullscreen"]);
+        proc.start(7, "firefox", &["/usr/lib/firefox/firefox"]);
+        fs::create_dir_all(proc.0.join("self")).unwrap();
+
+        let processes = scan_processes(&proc.root());
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].exe.as_deref(), Some("firefox"));
+        assert_eq!(processes[1].cmdline, "C:\\Games\\Doom.exe -fullscreen");
+
+        assert!(rule("a", "FireFox", MatchOn::Name, "p", 0).matches(&processes[0]));
+        assert!(!rule("b", "doom.exe", MatchOn::Name, "p", 0).matches(&processes[1]));
+        assert!(rule("c", "doom.exe", MatchOn::Cmdline, "p", 0).matches(&processes[1]));
+    }
+
+    #[tokio::test]
+    async fn test_switches_by_priority_and_restores_on_exit() {
+        let proc = FakeProc::new("proc-switch");
+        let dir = TempDir::new("app-switch");
+        let profiles = profiles(&dir);
+        let backend = MockBackend::default();
+        backend.set("1", "brightness", "40").await.unwrap();
+        let mut watcher = watcher(&proc, &dir);
+        let rules = [
+            rule("Video", "mpv", MatchOn::Name, "Bright", 0),
+            rule("Game", "game.exe", MatchOn::Cmdline, "FPS", 10),
+        ];
+        let settings = || backend.monitor("1").unwrap().settings;
+
+        assert!(watcher.poll(&backend, &rules, &profiles).await.is_empty());
+        proc.start(100, "mpv", &["mpv", "film.mkv"]);
+        proc.start(200, "wine64-preloader", &["Z:\\game.exe"]);
+        let switches = watcher.poll(&backend, &rules, &profiles).await;
+        assert_eq!(switches[0].rule.as_deref(), Some("Game"));
+        assert_eq!(settings().game_mode, GameMode::Fps);
+        assert!(settings().zero_latency);
+        assert_eq!(settings().brightness, 40);
+
+        // Nothing changes while the same rule keeps winning
+        let writes = backend.writes().len();
+        assert!(watcher.poll(&backend, &rules, &profiles).await.is_empty());
+        assert_eq!(backend.writes().len(), writes);
+
+        proc.exit(200);
+        let switches = watcher.poll(&backend, &rules, &profiles).await;
+        assert_eq!(switches[0].profile.as_deref(), Some("Bright"));
+        assert_eq!(settings().brightness, 100);
+
+        proc.exit(100);
+        let switches = watcher.poll(&backend, &rules, &profiles).await;
+        assert_eq!(switches[0].rule, None);
+        assert!(switches[0].results.iter().all(|r| r.success));
+        assert_eq!(settings().brightness, 40);
+        assert_eq!(settings().game_mode, GameMode::User);
+        assert!(!settings().zero_latency);
+    }
+
+    #[tokio::test]
+    async fn test_missing_profile_is_reported() {
+        let proc = FakeProc::new("proc-missing");
+        let dir = TempDir::new("app-missing");
+        let profiles = profiles(&dir);
+        let backend = MockBackend::default();
+        let mut watcher = watcher(&proc, &dir);
+        proc.start(1, "game", &["game"]);
+
+        let rules = [rule("Game", "game", MatchOn::Name, "Nope", 0)];
+        let switches = watcher.poll(&backend, &rules, &profiles).await;
+        assert_eq!(switches[0].results[0].field, "profile");
+        assert!(!switches[0].results[0].success);
+        assert!(watcher.poll(&backend, &rules, &profiles).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restores_last_known_mystic_light_on_exit() {
+        let proc = FakeProc::new("proc-mystic");
+        let dir = TempDir::new("app-mystic");
+        let profiles = profiles(&dir);
+        let glow = MysticLightConfig {
+            led_group: "0".to_string(),
+            mode: MysticLightMode::Breathing,
+            colors: vec!["#ff0000".to_string(), "#0000ff".to_string()],
+        };
+        profiles
+            .save(Profile {
+                name: "Glow".to_string(),
+                settings: PartialMonitorSettings::default(),
+                mystic_light: Some(glow),
+            })
+            .unwrap();
+        let mystic_light = Arc::new(MysticLightStore::load(dir.join(MYSTIC_LIGHT_FILE)));
+        let mock = Arc::new(MockBackend::default());
+        let backend = MysticLightRecorder::new(mock.clone(), mystic_light.clone());
+        backend.mystic("1", "0:static:0x00FF00").await.unwrap();
+        let mut watcher = AppWatcher::new(proc.root(), mystic_light);
+        let rules = [rule("Game", "game", MatchOn::Name, "Glow", 0)];
+        let leds = || mock.monitor("1").unwrap().mystic;
+
+        proc.start(1, "game", &["game"]);
+        watcher.poll(&backend, &rules, &profiles).await;
+        assert_eq!(leds().as_deref(), Some("0:breathing:0xFF0000,0x0000FF"));
+
+        proc.exit(1);
+        let switches = watcher.poll(&backend, &rules, &profiles).await;
+        assert_eq!(switches[0].results.len(), 1);
+        assert!(switches[0].results[0].success);
+        assert_eq!(leds().as_deref(), Some("0:static:0x00FF00"));
+    }
+}
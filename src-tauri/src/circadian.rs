@@ -0,0 +1,408 @@
+//! Circadian mode: brightness and color temperature that follow the sun
+//!
+//! Day targets apply while the sun is more than 6° above the horizon and
+//! night targets once it is more than 6° below (civil dusk), with values
+//! interpolated in between. The sun's position is computed offline from the
+//! configured location. Values are only written when the target changes,
+//! so manual changes hold until the next step of dawn or dusk.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::{ColorPreset, ColorRgb, MsigdParser, SettingValue, SharedBackend};
+use crate::profiles::write_json;
+use crate::solar::solar_elevation;
+
+/// File name of the circadian config inside the app config dir
+pub const CIRCADIAN_FILE: &str = "circadian.json";
+
+/// Sun elevation in degrees above which it is full day, and below whose
+/// negative it is full night
+const TWILIGHT_ELEVATION: f64 = 6.0;
+
+const STORE_VERSION: u32 = 1;
+
+/// Brightness and color at one end of the day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircadianTarget {
+    pub brightness: u8,
+    pub color_rgb: ColorRgb,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircadianConfig {
+    pub enabled: bool,
+    /// Degrees, positive north
+    pub latitude: f64,
+    /// Degrees, positive east
+    pub longitude: f64,
+    /// Stable ids of the monitors to drive
+    pub monitor_ids: Vec<String>,
+    pub day: CircadianTarget,
+    pub night: CircadianTarget,
+    /// Seconds between updates
+    pub step_secs: u32,
+}
+
+impl Default for CircadianConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            latitude: 0.0,
+            longitude: 0.0,
+            monitor_ids: Vec::new(),
+            day: CircadianTarget {
+                brightness: 80,
+                color_rgb: ColorRgb { r: 50, g: 50, b: 50 },
+            },
+            night: CircadianTarget {
+                brightness: 30,
+                color_rgb: ColorRgb { r: 50, g: 40, b: 30 },
+            },
+            step_secs: 60,
+        }
+    }
+}
+
+impl CircadianConfig {
+    pub fn validate(&self) -> Result<(), MsigdError> {
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(MsigdError::InvalidValue(
+                "Latitude must be -90 to 90".to_string(),
+            ));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(MsigdError::InvalidValue(
+                "Longitude must be -180 to 180".to_string(),
+            ));
+        }
+        if self.step_secs == 0 {
+            return Err(MsigdError::InvalidValue(
+                "Step interval must be at least 1 second".to_string(),
+            ));
+        }
+        for target in [&self.day, &self.night] {
+            SettingValue::Brightness(target.brightness).to_msigd()?;
+            SettingValue::ColorRgb(target.color_rgb.clone()).to_msigd()?;
+        }
+        Ok(())
+    }
+
+    /// How far into the day it is at `at`, from 0 (night) to 1 (day)
+    pub fn daylight(&self, at: DateTime<Utc>) -> f64 {
+        let elevation = solar_elevation(at, self.latitude, self.longitude);
+        ((elevation + TWILIGHT_ELEVATION) / (2.0 * TWILIGHT_ELEVATION)).clamp(0.0, 1.0)
+    }
+
+    /// Brightness and color for `at`
+    pub fn target_at(&self, at: DateTime<Utc>) -> CircadianTarget {
+        let daylight = self.daylight(at);
+        let lerp = |night: u8, day: u8| {
+            (f64::from(night) + (f64::from(day) - f64::from(night)) * daylight).round() as u8
+        };
+        let (day, night) = (&self.day.color_rgb, &self.night.color_rgb);
+        CircadianTarget {
+            brightness: lerp(self.night.brightness, self.day.brightness),
+            color_rgb: ColorRgb {
+                r: lerp(night.r, day.r),
+                g: lerp(night.g, day.g),
+                b: lerp(night.b, day.b),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    config: CircadianConfig,
+}
+
+/// Circadian config kept in memory and mirrored to a JSON file
+pub struct CircadianStore {
+    path: PathBuf,
+    config: Mutex<CircadianConfig>,
+    changed: Notify,
+}
+
+impl CircadianStore {
+    /// Load the config from `path`, starting disabled if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let config = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.config)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            config: Mutex::new(config),
+            changed: Notify::new(),
+        }
+    }
+
+    pub fn get(&self) -> CircadianConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Save the config, keeping the current one if it can't be written
+    pub fn set(&self, config: CircadianConfig) -> Result<(), MsigdError> {
+        config.validate()?;
+        let mut current = self.config.lock().unwrap();
+        let file = StoreFile {
+            version: STORE_VERSION,
+            config,
+        };
+        write_json(&self.path, &file)?;
+        *current = file.config;
+        self.changed.notify_one();
+        Ok(())
+    }
+
+    /// Resolves once the config was set since the last call
+    pub async fn changed(&self) {
+        self.changed.notified().await;
+    }
+}
+
+/// Writes circadian targets, skipping values a monitor already got
+#[derive(Default)]
+pub struct Circadian {
+    /// Config the applied values were written for
+    config: Option<CircadianConfig>,
+    /// Last (brightness, r, g, b) written per monitor
+    applied: HashMap<String, (u8, u8, u8, u8)>,
+}
+
+impl Circadian {
+    /// Move every configured monitor to the target for `at`
+    ///
+    /// Returns the monitors that failed; they are fully rewritten next step,
+    /// as is every monitor after the config changed.
+    pub async fn step(
+        &mut self,
+        backend: &dyn MonitorBackend,
+        config: &CircadianConfig,
+        at: DateTime<Utc>,
+    ) -> Vec<(String, MsigdError)> {
+        if !config.enabled {
+            self.applied.clear();
+            return Vec::new();
+        }
+        if self.config.as_ref() != Some(config) {
+            self.applied.clear();
+            self.config = Some(config.clone());
+        }
+        let target = config.target_at(at);
+        let mut errors = Vec::new();
+        for monitor_id in &config.monitor_ids {
+            if let Err(e) = self.apply(backend, monitor_id, &target).await {
+                self.applied.remove(monitor_id);
+                errors.push((monitor_id.clone(), e));
+            }
+        }
+        errors
+    }
+
+    async fn apply(
+        &mut self,
+        backend: &dyn MonitorBackend,
+        monitor_id: &str,
+        target: &CircadianTarget,
+    ) -> Result<(), MsigdError> {
+        let rgb = &target.color_rgb;
+        let values = (target.brightness, rgb.r, rgb.g, rgb.b);
+        let last = self.applied.get(monitor_id).copied();
+        let rgb_changed = last.map(|l| (l.1, l.2, l.3)) != Some((values.1, values.2, values.3));
+        // RGB values only take effect with the custom color preset, which
+        // may have been switched away from since it was last sent
+        if last.is_none() || (rgb_changed && !custom_preset(backend, monitor_id).await?) {
+            write(backend, monitor_id, SettingValue::ColorPreset(ColorPreset::Custom)).await?;
+        }
+        if last.map(|l| l.0) != Some(values.0) {
+            write(backend, monitor_id, SettingValue::Brightness(values.0)).await?;
+        }
+        if rgb_changed {
+            write(backend, monitor_id, SettingValue::ColorRgb(rgb.clone())).await?;
+        }
+        self.applied.insert(monitor_id.to_string(), values);
+        Ok(())
+    }
+}
+
+/// Whether the monitor reports the custom color preset
+async fn custom_preset(backend: &dyn MonitorBackend, monitor_id: &str) -> Result<bool, MsigdError> {
+    let output = backend
+        .query(monitor_id)
+        .await
+        .map_err(|e| e.with_monitor(monitor_id))?;
+    let queried = MsigdParser::parse_query(&output)?;
+    Ok(queried.settings.color_preset == ColorPreset::Custom)
+}
+
+async fn write(
+    backend: &dyn MonitorBackend,
+    monitor_id: &str,
+    setting: SettingValue,
+) -> Result<(), MsigdError> {
+    let (def, value) = setting.to_msigd()?;
+    backend
+        .set(monitor_id, def.flag, &value)
+        .await
+        .map_err(|e| e.with_setting(def.flag).with_monitor(monitor_id))?;
+    Ok(())
+}
+
+/// Drive circadian brightness and color in the background
+///
+/// A new config is applied right away instead of after the current step.
+pub fn spawn(backend: SharedBackend, store: Arc<CircadianStore>) {
+    tauri::async_runtime::spawn(async move {
+        let mut circadian = Circadian::default();
+        loop {
+            let config = store.get();
+            for (monitor_id, e) in circadian.step(backend.as_ref(), &config, Utc::now()).await {
+                eprintln!("Circadian update failed on {}: {}", monitor_id, e);
+            }
+            let step = Duration::from_secs(config.step_secs.max(1).into());
+            tokio::select! {
+                _ = tokio::time::sleep(step) => {}
+                _ = store.changed() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::mock::MockBackend;
+    use crate::test_support::TempDir;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        format!("2024-06-21T{}Z", time).parse().unwrap()
+    }
+
+    fn berlin() -> CircadianConfig {
+        CircadianConfig {
+            enabled: true,
+            latitude: 52.52,
+            longitude: 13.405,
+            monitor_ids: vec!["1".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_targets_follow_the_sun() {
+        let config = berlin();
+
+        let noon = config.target_at(utc("11:00:00"));
+        assert_eq!(noon.brightness, 80);
+        let midnight = config.target_at(utc("23:00:00"));
+        assert_eq!(midnight.brightness, 30);
+        assert_eq!(midnight.color_rgb.b, 30);
+
+        // Sunset in Berlin is around 19:33 UTC; dusk sits between the targets
+        let dusk = config.target_at(utc("19:35:00"));
+        assert!((31..80).contains(&dusk.brightness), "{}", dusk.brightness);
+        assert!((31..50).contains(&dusk.color_rgb.b), "{}", dusk.color_rgb.b);
+    }
+
+    #[tokio::test]
+    async fn test_step_writes_only_changes() {
+        let backend = MockBackend::default();
+        let mut circadian = Circadian::default();
+        let config = berlin();
+
+        assert!(circadian.step(&backend, &config, utc("11:00:00")).await.is_empty());
+        let flags: Vec<String> = backend.writes().into_iter().map(|w| w.1).collect();
+        assert_eq!(flags, ["color_preset", "brightness", "color_rgb"]);
+
+        circadian.step(&backend, &config, utc("11:01:00")).await;
+        assert_eq!(backend.writes().len(), 3);
+
+        circadian.step(&backend, &config, utc("23:00:00")).await;
+        let monitor = backend.monitor("1").unwrap();
+        assert_eq!(monitor.settings.brightness, 30);
+        assert_eq!(monitor.settings.color_rgb.g, 40);
+        assert_eq!(backend.writes().len(), 5);
+
+        let disabled = CircadianConfig {
+            enabled: false,
+            ..config
+        };
+        circadian.step(&backend, &disabled, utc("11:00:00")).await;
+        assert_eq!(backend.writes().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_step_resends_color_preset() {
+        let backend = MockBackend::default();
+        let mut circadian = Circadian::default();
+        let config = berlin();
+        let presets = || {
+            let writes = backend.writes().into_iter();
+            writes.filter(|w| w.1 == "color_preset").count()
+        };
+
+        circadian.step(&backend, &config, utc("11:00:00")).await;
+        assert_eq!(presets(), 1);
+        circadian.step(&backend, &config, utc("23:00:00")).await;
+        assert_eq!(presets(), 1);
+
+        // Switched away by hand
+        backend.set("1", "color_preset", "normal").await.unwrap();
+        circadian.step(&backend, &config, utc("11:00:00")).await;
+        assert_eq!(presets(), 3);
+        assert_eq!(backend.monitor("1").unwrap().settings.color_preset, ColorPreset::Custom);
+
+        let mut changed = config.clone();
+        changed.step_secs = 30;
+        circadian.step(&backend, &changed, utc("11:00:00")).await;
+        assert_eq!(presets(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_store_wakes_on_change() {
+        let dir = TempDir::new("circadian");
+        let path = dir.join(CIRCADIAN_FILE);
+        let store = CircadianStore::load(path.clone());
+        let wait = Duration::from_millis(50);
+        assert!(tokio::time::timeout(wait, store.changed()).await.is_err());
+
+        store.set(berlin()).unwrap();
+        assert!(tokio::time::timeout(wait, store.changed()).await.is_ok());
+        assert!(CircadianStore::load(path).get().enabled);
+
+        // A config that can't be saved is not applied either
+        dir.write("file", "");
+        let unsaved = CircadianStore::load(dir.join("file").join(CIRCADIAN_FILE));
+        assert!(unsaved.set(berlin()).is_err());
+        assert!(!unsaved.get().enabled);
+        assert!(tokio::time::timeout(wait, unsaved.changed()).await.is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_config() {
+        let mut config = berlin();
+        config.latitude = 95.0;
+        assert!(config.validate().is_err());
+
+        let mut config = berlin();
+        config.night.brightness = 120;
+        assert!(config.validate().is_err());
+        assert!(berlin().validate().is_ok());
+    }
+}
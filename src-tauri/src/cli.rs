@@ -0,0 +1,371 @@
+//! Headless command-line interface
+//!
+//! Lets scripts and window-manager keybindings control monitors through the
+//! same backend, parser and validation as the GUI without starting a webview.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{self, MonitorBackend, SharedBackend};
+use crate::msigd::batch::SettingsBatch;
+use crate::msigd::registry::{self, SettingDef};
+use crate::msigd::{FieldResult, MsigdParser, PartialMonitorSettings, SettingValue};
+use crate::mystic_light::{MysticLightRecorder, MysticLightStore, MYSTIC_LIGHT_FILE};
+use crate::profiles::{ProfileStore, PROFILES_FILE};
+
+/// Must match `identifier` in tauri.conf.json so the CLI sees GUI profiles
+const APP_IDENTIFIER: &str = "io.github.aydiler.msigd-gui";
+
+const USAGE: &str = "\
+Usage: msigd-gui [COMMAND] [--monitor ID] [--json]
+
+Without a command the GUI is started.
+
+Commands:
+  list                    List connected monitors
+  get <setting>           Print the current value of a setting
+  set <setting> <value>   Change a setting (e.g. set brightness 40)
+  apply-profile <name>    Apply a saved profile
+  help                    Show this message
+
+Options:
+  --monitor ID  Monitor id from `list` or msigd index (default: 1)
+  --json        Print machine-readable JSON";
+
+/// A CLI subcommand
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    List,
+    Get { setting: String },
+    Set { setting: String, value: String },
+    ApplyProfile { name: String },
+    Help,
+}
+
+/// Text to print and whether every requested change succeeded
+#[derive(Debug)]
+pub struct Output {
+    pub text: String,
+    pub success: bool,
+}
+
+/// A parsed command line
+#[derive(Debug, PartialEq)]
+pub struct Invocation {
+    pub command: Command,
+    pub monitor: String,
+    pub json: bool,
+}
+
+/// Parse arguments (without the program name)
+///
+/// Returns `Ok(None)` when no subcommand is present and the GUI should run.
+pub fn parse_args(args: &[String]) -> Result<Option<Invocation>, String> {
+    let mut monitor = "1".to_string();
+    let mut json = false;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--monitor" => {
+                monitor = iter
+                    .next()
+                    .ok_or("--monitor requires a value")?
+                    .to_string();
+            }
+            "--help" | "-h" => positional.insert(0, "help".to_string()),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let Some((name, rest)) = positional.split_first() else {
+        return Ok(None);
+    };
+    let command = match (name.as_str(), rest) {
+        ("list", []) => Command::List,
+        ("get", [setting]) => Command::Get {
+            setting: setting.clone(),
+        },
+        ("set", [setting, value]) => Command::Set {
+            setting: setting.clone(),
+            value: value.clone(),
+        },
+        ("apply-profile", [name]) => Command::ApplyProfile { name: name.clone() },
+        ("help", _) => Command::Help,
+        ("list" | "get" | "set" | "apply-profile", _) => {
+            return Err(format!("Wrong number of arguments for '{}'", name));
+        }
+        // Leave unknown arguments to the GUI
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Invocation {
+        command,
+        monitor,
+        json,
+    }))
+}
+
+/// Run a CLI subcommand, returning the process exit code
+///
+/// Returns `None` when no subcommand was given and the GUI should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    let invocation = match parse_args(args) {
+        Ok(Some(invocation)) => invocation,
+        Ok(None) => return None,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return Some(2);
+        }
+    };
+    if invocation.command == Command::Help {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start tokio runtime");
+    // Profiles applied here update the GUI's last known Mystic Light config
+    let mystic_light = Arc::new(MysticLightStore::load(config_dir().join(MYSTIC_LIGHT_FILE)));
+    let backend: SharedBackend =
+        Arc::new(MysticLightRecorder::new(backend::from_env(), mystic_light));
+    let profiles = ProfileStore::load(config_dir().join(PROFILES_FILE));
+
+    match runtime.block_on(execute(&invocation, backend.as_ref(), &profiles)) {
+        Ok(output) if output.success => {
+            println!("{}", output.text);
+            Some(0)
+        }
+        Ok(output) => {
+            // Per-field failures go to stdout in JSON mode so scripts can parse them
+            if invocation.json {
+                println!("{}", output.text);
+            } else {
+                eprintln!("{}", output.text);
+            }
+            Some(1)
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            Some(1)
+        }
+    }
+}
+
+/// Execute a parsed invocation and return the text to print
+pub async fn execute(
+    invocation: &Invocation,
+    backend: &dyn MonitorBackend,
+    profiles: &ProfileStore,
+) -> Result<Output, MsigdError> {
+    let monitor = invocation.monitor.as_str();
+    let text = match &invocation.command {
+        Command::List => {
+            let monitors = backend::list_monitors(backend).await?;
+            if invocation.json {
+                json!(monitors).to_string()
+            } else {
+                monitors
+                    .iter()
+                    .map(|m| {
+                        format!("{}: {} ({}, firmware {})", m.id, m.model, m.identity, m.firmware)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+        Command::Get { setting } => {
+            let def = setting_def(setting)?;
+            let queried = MsigdParser::parse_query(&backend.query(monitor).await?)?;
+            if !queried.reported.iter().any(|f| f == def.field) {
+                return Err(MsigdError::UnsupportedSetting(def.label.to_string())
+                    .with_setting(def.flag)
+                    .with_monitor(monitor));
+            }
+            let value = json!(queried.settings)[def.field].clone();
+            if invocation.json {
+                json!({ "setting": setting, "value": value }).to_string()
+            } else {
+                display_value(&value)
+            }
+        }
+        Command::Set { setting, value } => {
+            let partial = parse_setting(setting, value)?;
+            let batch = SettingsBatch::from_partial(&partial);
+            if let Some(rejected) = batch.rejected().first() {
+                return Err(MsigdError::InvalidValue(
+                    rejected.error.clone().unwrap_or_default(),
+                ));
+            }
+            let results = batch.apply(backend, monitor).await;
+            return Ok(report(&results, invocation.json));
+        }
+        Command::ApplyProfile { name } => {
+            let profile = profiles.get(name)?;
+            let results = profile.apply(backend, monitor).await;
+            return Ok(report(&results, invocation.json));
+        }
+        Command::Help => USAGE.to_string(),
+    };
+    Ok(Output {
+        text,
+        success: true,
+    })
+}
+
+/// Format per-field results
+fn report(results: &[FieldResult], json: bool) -> Output {
+    let text = if json {
+        json!(results).to_string()
+    } else {
+        results
+            .iter()
+            .map(|r| match &r.error {
+                None => format!("{}: ok", r.field),
+                Some(error) => format!("{}: {}", r.field, error),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Output {
+        text,
+        success: results.iter().all(|r| r.success),
+    }
+}
+
+/// Look up a CLI setting name (msigd flag or field name)
+fn setting_def(setting: &str) -> Result<&'static SettingDef, MsigdError> {
+    registry::lookup(setting)
+        .ok_or_else(|| MsigdError::InvalidValue(format!("Unknown setting: {}", setting)))
+}
+
+/// Build a single-field partial settings from a CLI value in msigd syntax
+///
+/// Values are validated like the GUI's `set_setting`, so choices must be
+/// given by name rather than by numeric code.
+fn parse_setting(setting: &str, value: &str) -> Result<PartialMonitorSettings, MsigdError> {
+    let tagged = serde_json::to_value(SettingValue::parse(setting_def(setting)?.flag, value)?)
+        .map_err(|e| MsigdError::ParseError(e.to_string()))?;
+    let field = tagged["setting"].as_str().unwrap_or_default();
+    serde_json::from_value(json!({ field: tagged["value"] }))
+        .map_err(|e| MsigdError::ParseError(e.to_string()))
+}
+
+/// Human-readable form of a setting value
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Bool(b) => if *b { "on" } else { "off" }.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(rgb) => ["r", "g", "b"]
+            .iter()
+            .map(|c| rgb.get(*c).map(Value::to_string).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(","),
+        other => other.to_string(),
+    }
+}
+
+/// Same directory Tauri's `app_config_dir` resolves to on Linux
+fn config_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join(APP_IDENTIFIER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::{AlarmClock, ScreenSize};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn invocation(line: &str) -> Invocation {
+        parse_args(&args(line)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&[]).unwrap(), None);
+        assert_eq!(
+            invocation("set brightness 40 --monitor 2 --json"),
+            Invocation {
+                command: Command::Set {
+                    setting: "brightness".to_string(),
+                    value: "40".to_string(),
+                },
+                monitor: "2".to_string(),
+                json: true,
+            }
+        );
+        assert!(parse_args(&args("get")).is_err());
+        assert!(parse_args(&args("list --monitor")).is_err());
+    }
+
+    #[test]
+    fn test_parse_setting_values() {
+        assert_eq!(parse_setting("brightness", "40").unwrap().brightness, Some(40));
+        assert_eq!(parse_setting("eye_saver", "on").unwrap().eye_saver, Some(true));
+        assert_eq!(
+            parse_setting("alarm_clock", "1").unwrap().alarm_clock,
+            Some(AlarmClock::One)
+        );
+        assert_eq!(
+            parse_setting("screen_size", "24").unwrap().screen_size,
+            Some(ScreenSize::Size24)
+        );
+        assert_eq!(parse_setting("color_rgb", "10,20,30").unwrap().color_rgb.unwrap().g, 20);
+        assert!(parse_setting("game_mode", "turbo").is_err());
+        // Positional codes are rejected like in the GUI
+        assert!(parse_setting("screen_size", "3").is_err());
+        assert!(parse_setting("kvm", "1").is_err());
+        assert!(parse_setting("no_such_setting", "on").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_set_and_get() {
+        let backend = MockBackend::default();
+        let profiles = ProfileStore::load(PathBuf::from("/nonexistent/profiles.json"));
+
+        execute(&invocation("set refresh_display on"), &backend, &profiles)
+            .await
+            .unwrap();
+        let output = execute(&invocation("get refresh_display"), &backend, &profiles)
+            .await
+            .unwrap();
+        assert_eq!(output.text, "on");
+
+        let output = execute(&invocation("get color_rgb --json"), &backend, &profiles)
+            .await
+            .unwrap();
+        let output: Value = serde_json::from_str(&output.text).unwrap();
+        assert_eq!(output["value"], json!({ "r": 50, "g": 50, "b": 50 }));
+
+        let result = execute(&invocation("set sharpness 9"), &backend, &profiles).await;
+        assert!(matches!(result.unwrap_err().root(), MsigdError::InvalidValue(_)));
+    }
+
+    #[tokio::test]
+    async fn test_get_unreported_setting_fails() {
+        let monitor = MockMonitor::new("SN1", "MAG274QRF-QD").without(&["kvm"]);
+        let backend = MockBackend::new(vec![monitor]);
+        let profiles = ProfileStore::load(PathBuf::from("/nonexistent/profiles.json"));
+
+        let error = execute(&invocation("get kvm"), &backend, &profiles)
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), "unsupported_setting");
+        assert!(execute(&invocation("get brightness"), &backend, &profiles).await.is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+//! Ambient light auto-brightness Tauri commands
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::ambient::{self, AmbientConfig, AmbientStore};
+use crate::circadian::CircadianStore;
+use crate::error::MsigdError;
+
+/// A light sensor with its current reading
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LightSensorInfo {
+    pub device: String,
+    pub name: String,
+    /// `None` when the sensor can't be read
+    pub lux: Option<f64>,
+}
+
+/// Current auto-brightness config
+#[command]
+pub fn get_ambient_config(ambient: State<'_, Arc<AmbientStore>>) -> AmbientConfig {
+    ambient.get()
+}
+
+/// Replace the auto-brightness config
+///
+/// Fails for monitors that circadian mode already drives.
+#[command]
+pub fn set_ambient_config(
+    ambient: State<'_, Arc<AmbientStore>>,
+    circadian: State<'_, Arc<CircadianStore>>,
+    config: AmbientConfig,
+) -> Result<(), MsigdError> {
+    config.check_conflicts(&circadian.get())?;
+    ambient.set(config)
+}
+
+/// IIO light sensors on this machine and what they read right now
+#[command]
+pub fn list_light_sensors() -> Vec<LightSensorInfo> {
+    let sysfs = Path::new("/sys");
+    ambient::light_sensors(sysfs)
+        .into_iter()
+        .map(|sensor| LightSensorInfo {
+            lux: ambient::read_lux(sysfs, &sensor.device).ok(),
+            device: sensor.device,
+            name: sensor.name,
+        })
+        .collect()
+}
@@ -0,0 +1,32 @@
+//! Per-application profile switching Tauri commands
+
+use std::sync::Arc;
+
+use tauri::{command, State};
+
+use crate::app_profiles::{AppRule, AppRuleStore};
+use crate::error::MsigdError;
+
+/// List all app rules
+#[command]
+pub fn list_app_rules(app_rules: State<'_, Arc<AppRuleStore>>) -> Vec<AppRule> {
+    app_rules.list()
+}
+
+/// Save a rule, replacing any existing rule with the same name
+#[command]
+pub fn save_app_rule(
+    app_rules: State<'_, Arc<AppRuleStore>>,
+    rule: AppRule,
+) -> Result<(), MsigdError> {
+    app_rules.save(rule)
+}
+
+/// Delete a rule by name
+#[command]
+pub fn delete_app_rule(
+    app_rules: State<'_, Arc<AppRuleStore>>,
+    name: String,
+) -> Result<(), MsigdError> {
+    app_rules.delete(&name)
+}
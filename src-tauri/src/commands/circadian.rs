@@ -0,0 +1,53 @@
+//! Circadian mode Tauri commands
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::ambient::AmbientStore;
+use crate::circadian::{CircadianConfig, CircadianStore};
+use crate::error::MsigdError;
+use crate::solar;
+
+/// Today's sunrise and sunset at a location
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SunTimesInfo {
+    /// Seconds since the Unix epoch, `None` during polar day or night
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+    pub polar_day: bool,
+}
+
+/// Current circadian mode config
+#[command]
+pub fn get_circadian_config(circadian: State<'_, Arc<CircadianStore>>) -> CircadianConfig {
+    circadian.get()
+}
+
+/// Replace the circadian mode config
+///
+/// Fails for monitors that auto-brightness already drives.
+#[command]
+pub fn set_circadian_config(
+    circadian: State<'_, Arc<CircadianStore>>,
+    ambient: State<'_, Arc<AmbientStore>>,
+    config: CircadianConfig,
+) -> Result<(), MsigdError> {
+    ambient.get().check_conflicts(&config)?;
+    circadian.set(config)
+}
+
+/// Sunrise and sunset for today's local date, computed offline
+#[command]
+pub fn get_sun_times(latitude: f64, longitude: f64) -> SunTimesInfo {
+    let times = solar::sun_times(Local::now().date_naive(), latitude, longitude);
+    let timestamp = |time: Option<DateTime<Utc>>| time.map(|t| t.timestamp());
+    SunTimesInfo {
+        sunrise: timestamp(times.sunrise),
+        sunset: timestamp(times.sunset),
+        polar_day: times.polar_day,
+    }
+}
@@ -0,0 +1,22 @@
+//! Diagnostics Tauri commands
+
+use tauri::{command, State};
+
+use crate::diagnostics::{self, DiagnosticReport, SystemPaths};
+use crate::error::MsigdError;
+use crate::msigd::{backend, SharedBackend};
+
+/// Check msigd, hidraw permissions and udev rules for every monitor
+#[command]
+pub async fn diagnose(backend: State<'_, SharedBackend>) -> Result<DiagnosticReport, MsigdError> {
+    let msigd_installed = backend.check_available().await?;
+    // Listing works without hidraw access, so failures here are not fatal
+    let monitors = backend::monitors(backend.as_ref()).await.unwrap_or_default();
+    Ok(DiagnosticReport::collect(&SystemPaths::default(), msigd_installed, &monitors).await)
+}
+
+/// Install the udev rule for MSI monitors, prompting for authentication
+#[command]
+pub async fn install_udev_rule() -> Result<(), MsigdError> {
+    diagnostics::install_udev_rule().await
+}
@@ -1,3 +1,10 @@
 //! Tauri command handlers
 
+pub mod ambient;
+pub mod app_profiles;
+pub mod circadian;
+pub mod diagnostics;
 pub mod monitor;
+pub mod profiles;
+pub mod restore;
+pub mod schedule;
@@ -1,149 +1,314 @@
 //! Monitor-related Tauri commands
 
-use tauri::command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tauri::{command, AppHandle, Emitter, State};
 
 use crate::error::MsigdError;
-use crate::msigd::{MsigdExecutor, MsigdParser, Monitor, MonitorSettings};
+use crate::msigd::backend;
+use crate::msigd::batch::SettingsBatch;
+use crate::msigd::capabilities::MonitorCapabilities;
+use crate::msigd::mystic::{self, MysticLightModeInfo};
+use crate::msigd::registry::{self, SettingDef};
+use crate::msigd::{
+    ColorRgb, FieldResult, Monitor, MsigdParser, MysticLightConfig, PartialMonitorSettings,
+    QueriedSettings, SettingValue, SharedBackend,
+};
+use crate::mystic_light::MysticLightStore;
+use crate::names::MonitorNames;
+use crate::transition::{Easing, TransitionOutcome, Transitions, TRANSITION_PROGRESS};
 
 /// List all connected MSI monitors
+///
+/// Monitor ids are stable across reboots and replugging; `index` is the
+/// current msigd index.
 #[command]
-pub fn list_monitors() -> Result<Vec<Monitor>, MsigdError> {
-    let output = MsigdExecutor::list_monitors()?;
-    MsigdParser::parse_monitor_list(&output)
+pub async fn list_monitors(
+    backend: State<'_, SharedBackend>,
+    names: State<'_, MonitorNames>,
+) -> Result<Vec<Monitor>, MsigdError> {
+    let mut monitors = backend::list_monitors(backend.as_ref()).await?;
+    names.apply(&mut monitors);
+    Ok(monitors)
 }
 
-/// Get settings for a specific monitor
+/// Give a monitor a friendly name, or clear it with an empty name
 #[command]
-pub fn get_monitor_settings(monitor_id: String) -> Result<MonitorSettings, MsigdError> {
-    let output = MsigdExecutor::query_monitor(&monitor_id)?;
-    MsigdParser::parse_settings(&output)
+pub fn rename_monitor(
+    names: State<'_, MonitorNames>,
+    monitor_id: String,
+    name: String,
+) -> Result<(), MsigdError> {
+    names.set(&monitor_id, &name)
+}
+
+/// Get settings for a specific monitor
+///
+/// `reported` lists the settings the monitor actually returned; the others
+/// hold defaults and should not be shown. `lastKnownMysticLight` is the last
+/// config the app applied, since LED state cannot be read from the device.
+/// Settings come from the backend's query cache unless `force_refresh` is set.
+#[command]
+pub async fn get_monitor_settings(
+    backend: State<'_, SharedBackend>,
+    mystic_light: State<'_, Arc<MysticLightStore>>,
+    monitor_id: String,
+    force_refresh: Option<bool>,
+) -> Result<QueriedSettings, MsigdError> {
+    query_settings(
+        &backend,
+        &mystic_light,
+        &monitor_id,
+        force_refresh.unwrap_or(false),
+    )
+    .await
+}
+
+/// Query a monitor by stable id or msigd index and add its last known
+/// Mystic Light config, which is stored by stable id
+pub(crate) async fn query_settings(
+    backend: &SharedBackend,
+    mystic_light: &MysticLightStore,
+    monitor_id: &str,
+    force_refresh: bool,
+) -> Result<QueriedSettings, MsigdError> {
+    let monitor = find_monitor(backend, monitor_id).await?;
+    if force_refresh {
+        backend.invalidate(monitor_id).await;
+    }
+    let output = backend
+        .query(monitor_id)
+        .await
+        .map_err(|e| e.with_monitor(monitor_id))?;
+    let mut settings = MsigdParser::parse_query(&output)?;
+    mystic_light.merge(&monitor.id, &mut settings);
+    Ok(settings)
+}
+
+/// Get which settings a monitor supports
+#[command]
+pub async fn get_monitor_capabilities(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+) -> Result<MonitorCapabilities, MsigdError> {
+    let output = backend
+        .query(&monitor_id)
+        .await
+        .map_err(|e| e.with_monitor(&monitor_id))?;
+    let queried = MsigdParser::parse_query(&output)?;
+    Ok(MonitorCapabilities::detect(&queried.reported))
+}
+
+/// Look up a connected monitor by id
+pub(crate) async fn find_monitor(
+    backend: &SharedBackend,
+    monitor_id: &str,
+) -> Result<Monitor, MsigdError> {
+    backend::monitors(backend.as_ref())
+        .await?
+        .into_iter()
+        .find(|m| m.id == monitor_id || m.index == monitor_id)
+        .ok_or_else(|| MsigdError::MonitorNotFound(monitor_id.to_string()))
+}
+
+/// Apply several settings in a single msigd call
+///
+/// Invalid fields are reported individually and not sent to the monitor.
+#[command]
+pub async fn apply_settings(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    settings: PartialMonitorSettings,
+) -> Result<Vec<FieldResult>, MsigdError> {
+    let batch = SettingsBatch::from_partial(&settings);
+    Ok(batch.apply(backend.as_ref(), &monitor_id).await)
+}
+
+/// Describe every setting so the frontend can build its controls
+#[command]
+pub fn get_setting_schema() -> Vec<SettingDef> {
+    registry::SETTINGS.to_vec()
+}
+
+/// Describe every Mystic Light mode and how many colors it takes
+#[command]
+pub fn get_mystic_light_modes() -> Vec<MysticLightModeInfo> {
+    mystic::modes()
+}
+
+/// Set any setting described by the registry
+///
+/// The per-setting commands below are kept as shims for older frontends.
+#[command]
+pub async fn set_setting(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    setting: SettingValue,
+) -> Result<(), MsigdError> {
+    let (def, value) = setting.to_msigd()?;
+    backend
+        .set(&monitor_id, def.flag, &value)
+        .await
+        .map_err(|e| e.with_setting(def.flag).with_monitor(&monitor_id))?;
+    Ok(())
+}
+
+/// Move a numeric or RGB setting to `target` over `duration_ms`
+///
+/// Emits `transition-progress` after every step and resolves when the target
+/// is reached, or early with `cancelled` when another write to the same
+/// setting takes over.
+#[command]
+pub async fn transition_setting(
+    app: AppHandle,
+    transitions: State<'_, Arc<Transitions>>,
+    monitor_id: String,
+    setting: String,
+    target: Value,
+    duration_ms: u64,
+    easing: Easing,
+) -> Result<TransitionOutcome, MsigdError> {
+    transitions
+        .run(
+            &monitor_id,
+            &setting,
+            &target,
+            Duration::from_millis(duration_ms),
+            easing,
+            |progress| {
+                let _ = app.emit(TRANSITION_PROGRESS, progress);
+            },
+        )
+        .await
 }
 
 /// Set brightness (0-100)
 #[command]
-pub fn set_brightness(monitor_id: String, value: u8) -> Result<(), MsigdError> {
-    if value > 100 {
-        return Err(MsigdError::InvalidValue(
-            "Brightness must be 0-100".to_string(),
-        ));
-    }
-    MsigdExecutor::set_numeric(&monitor_id, "brightness", value)?;
-    Ok(())
+pub async fn set_brightness(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::Brightness(value)).await
 }
 
 /// Set contrast (0-100)
 #[command]
-pub fn set_contrast(monitor_id: String, value: u8) -> Result<(), MsigdError> {
-    if value > 100 {
-        return Err(MsigdError::InvalidValue(
-            "Contrast must be 0-100".to_string(),
-        ));
-    }
-    MsigdExecutor::set_numeric(&monitor_id, "contrast", value)?;
-    Ok(())
+pub async fn set_contrast(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::Contrast(value)).await
 }
 
 /// Set sharpness (0-5)
 #[command]
-pub fn set_sharpness(monitor_id: String, value: u8) -> Result<(), MsigdError> {
-    if value > 5 {
-        return Err(MsigdError::InvalidValue(
-            "Sharpness must be 0-5".to_string(),
-        ));
-    }
-    MsigdExecutor::set_numeric(&monitor_id, "sharpness", value)?;
-    Ok(())
+pub async fn set_sharpness(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::Sharpness(value)).await
 }
 
 /// Set response time
 #[command]
-pub fn set_response_time(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["normal", "fast", "fastest"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Response time must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "response_time", &value)?;
-    Ok(())
+pub async fn set_response_time(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("response_time", &value)?).await
 }
 
 /// Set eye saver mode
 #[command]
-pub fn set_eye_saver(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "eye_saver", value)?;
-    Ok(())
+pub async fn set_eye_saver(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::EyeSaver(enabled)).await
 }
 
 /// Check if msigd is available
 #[command]
-pub fn check_msigd_available() -> Result<bool, MsigdError> {
-    MsigdExecutor::check_available()
+pub async fn check_msigd_available(backend: State<'_, SharedBackend>) -> Result<bool, MsigdError> {
+    backend.check_available().await
 }
 
 /// Set color preset (cool, normal, warm, custom)
 #[command]
-pub fn set_color_preset(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["cool", "normal", "warm", "custom"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Color preset must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "color_preset", &value)?;
-    Ok(())
+pub async fn set_color_preset(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("color_preset", &value)?).await
 }
 
 /// Set color RGB values (0-100 each)
 #[command]
-pub fn set_color_rgb(monitor_id: String, r: u8, g: u8, b: u8) -> Result<(), MsigdError> {
-    if r > 100 || g > 100 || b > 100 {
-        return Err(MsigdError::InvalidValue(
-            "RGB values must be 0-100".to_string(),
-        ));
-    }
-    MsigdExecutor::set_color_rgb(&monitor_id, r, g, b)?;
-    Ok(())
+pub async fn set_color_rgb(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::ColorRgb(ColorRgb { r, g, b })).await
 }
 
 /// Set image enhancement mode
 #[command]
-pub fn set_image_enhancement(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["off", "weak", "medium", "strong", "strongest"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Image enhancement must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "image_enhancement", &value)?;
-    Ok(())
+pub async fn set_image_enhancement(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("image_enhancement", &value)?).await
 }
 
 /// Set HDCR (High Dynamic Contrast Ratio)
 #[command]
-pub fn set_hdcr(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "hdcr", value)?;
-    Ok(())
+pub async fn set_hdcr(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::Hdcr(enabled)).await
 }
 
 /// Set refresh rate display (show refresh rate on screen)
 #[command]
-pub fn set_refresh_rate_display(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    // msigd uses "refresh_display" not "refresh_rate_display"
-    MsigdExecutor::set_enum(&monitor_id, "refresh_display", value)?;
-    Ok(())
+pub async fn set_refresh_rate_display(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::RefreshRateDisplay(enabled)).await
 }
 
 /// Set Mystic Light LED configuration
+///
+/// The config is validated and converted to msigd's mystic syntax here; the
+/// backend remembers it as the last known config.
 #[command]
-pub fn set_mystic_light(monitor_id: String, config: String) -> Result<(), MsigdError> {
-    MsigdExecutor::set_mystic_light(&monitor_id, &config)?;
+pub async fn set_mystic_light(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    config: MysticLightConfig,
+) -> Result<(), MsigdError> {
+    let msigd_config = config
+        .to_msigd()
+        .map_err(|e| e.with_setting("mystic").with_monitor(&monitor_id))?;
+    backend
+        .mystic(&monitor_id, &msigd_config)
+        .await
+        .map_err(|e| e.with_setting("mystic").with_monitor(&monitor_id))?;
     Ok(())
 }
 
@@ -151,272 +316,259 @@ pub fn set_mystic_light(monitor_id: String, config: String) -> Result<(), MsigdE
 
 /// Set OSD transparency (0-5)
 #[command]
-pub fn set_osd_transparency(monitor_id: String, value: u8) -> Result<(), MsigdError> {
-    if value > 5 {
-        return Err(MsigdError::InvalidValue(
-            "OSD transparency must be 0-5".to_string(),
-        ));
-    }
-    MsigdExecutor::set_numeric(&monitor_id, "osd_transparency", value)?;
-    Ok(())
+pub async fn set_osd_transparency(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::OsdTransparency(value)).await
 }
 
 /// Set OSD timeout (0-30 seconds)
 #[command]
-pub fn set_osd_timeout(monitor_id: String, value: u8) -> Result<(), MsigdError> {
-    if value > 30 {
-        return Err(MsigdError::InvalidValue(
-            "OSD timeout must be 0-30".to_string(),
-        ));
-    }
-    MsigdExecutor::set_numeric(&monitor_id, "osd_timeout", value)?;
-    Ok(())
+pub async fn set_osd_timeout(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::OsdTimeout(value)).await
 }
 
 // Phase 2: MAG Core Settings
 
 /// Set night vision mode
 #[command]
-pub fn set_night_vision(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["off", "normal", "strong", "strongest", "ai"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Night vision must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "night_vision", &value)?;
-    Ok(())
+pub async fn set_night_vision(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("night_vision", &value)?).await
 }
 
 /// Set black tuner (0-20)
 #[command]
-pub fn set_black_tuner(monitor_id: String, value: u8) -> Result<(), MsigdError> {
-    if value > 20 {
-        return Err(MsigdError::InvalidValue(
-            "Black tuner must be 0-20".to_string(),
-        ));
-    }
-    MsigdExecutor::set_numeric(&monitor_id, "black_tuner", value)?;
-    Ok(())
+pub async fn set_black_tuner(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: u8,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::BlackTuner(value)).await
 }
 
 /// Set screen assistance (crosshair)
 #[command]
-pub fn set_screen_assistance(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = [
-        "off", "red1", "red2", "red3", "red4", "red5", "red6",
-        "white1", "white2", "white3", "white4", "white5", "white6",
-    ];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Screen assistance must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "screen_assistance", &value)?;
-    Ok(())
+pub async fn set_screen_assistance(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("screen_assistance", &value)?).await
 }
 
 /// Set refresh rate display position
 #[command]
-pub fn set_refresh_position(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["left_top", "right_top", "left_bottom", "right_bottom"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Refresh position must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "refresh_position", &value)?;
-    Ok(())
+pub async fn set_refresh_position(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("refresh_position", &value)?).await
 }
 
 /// Set alarm clock timer
 #[command]
-pub fn set_alarm_clock(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["off", "1", "2", "3", "4"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Alarm clock must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "alarm_clock", &value)?;
-    Ok(())
+pub async fn set_alarm_clock(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("alarm_clock", &value)?).await
 }
 
 /// Set alarm clock position
 #[command]
-pub fn set_alarm_position(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["left_top", "right_top", "left_bottom", "right_bottom"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Alarm position must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "alarm_position", &value)?;
-    Ok(())
+pub async fn set_alarm_position(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("alarm_position", &value)?).await
 }
 
 /// Set sound enable
 #[command]
-pub fn set_sound_enable(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "sound_enable", value)?;
-    Ok(())
+pub async fn set_sound_enable(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::SoundEnable(enabled)).await
 }
 
 // Phase 3: Performance Settings
 
 /// Set zero latency mode
 #[command]
-pub fn set_zero_latency(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "zero_latency", value)?;
-    Ok(())
+pub async fn set_zero_latency(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::ZeroLatency(enabled)).await
 }
 
 /// Set FreeSync
 #[command]
-pub fn set_free_sync(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "free_sync", value)?;
-    Ok(())
+pub async fn set_free_sync(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::FreeSync(enabled)).await
 }
 
 /// Set game mode
 #[command]
-pub fn set_game_mode(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["user", "fps", "racing", "rts", "rpg", "premium_color"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Game mode must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "game_mode", &value)?;
-    Ok(())
+pub async fn set_game_mode(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("game_mode", &value)?).await
 }
 
 /// Set pro mode
 #[command]
-pub fn set_pro_mode(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = [
-        "user", "reader", "cinema", "designer", "office", "srgb",
-        "adobe_rgb", "dci_p3", "eco", "anti_blue", "movie",
-    ];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Pro mode must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "pro_mode", &value)?;
-    Ok(())
+pub async fn set_pro_mode(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("pro_mode", &value)?).await
 }
 
 // Phase 4: Input/System Settings
 
 /// Set input source
 #[command]
-pub fn set_input(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["hdmi1", "hdmi2", "dp", "usbc"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Input must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "input", &value)?;
-    Ok(())
+pub async fn set_input(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("input", &value)?).await
 }
 
 /// Set auto scan
 #[command]
-pub fn set_auto_scan(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "auto_scan", value)?;
-    Ok(())
+pub async fn set_auto_scan(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::AutoScan(enabled)).await
 }
 
 /// Set screen info display
 #[command]
-pub fn set_screen_info(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "screen_info", value)?;
-    Ok(())
+pub async fn set_screen_info(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::ScreenInfo(enabled)).await
 }
 
 /// Set screen size
 #[command]
-pub fn set_screen_size(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["auto", "4:3", "16:9", "21:9", "1:1", "19", "24"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Screen size must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "screen_size", &value)?;
-    Ok(())
+pub async fn set_screen_size(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("screen_size", &value)?).await
 }
 
 /// Set power button behavior
 #[command]
-pub fn set_power_button(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["off", "standby"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Power button must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "power_button", &value)?;
-    Ok(())
+pub async fn set_power_button(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("power_button", &value)?).await
 }
 
 /// Set HDMI CEC
 #[command]
-pub fn set_hdmi_cec(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "hdmi_cec", value)?;
-    Ok(())
+pub async fn set_hdmi_cec(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::HdmiCec(enabled)).await
 }
 
 /// Set KVM mode
 #[command]
-pub fn set_kvm(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["auto", "upstream", "type_c"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "KVM must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "kvm", &value)?;
-    Ok(())
+pub async fn set_kvm(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("kvm", &value)?).await
 }
 
 /// Set audio source
 #[command]
-pub fn set_audio_source(monitor_id: String, value: String) -> Result<(), MsigdError> {
-    let valid_values = ["analog", "digital"];
-    if !valid_values.contains(&value.as_str()) {
-        return Err(MsigdError::InvalidValue(format!(
-            "Audio source must be one of: {}",
-            valid_values.join(", ")
-        )));
-    }
-    MsigdExecutor::set_enum(&monitor_id, "audio_source", &value)?;
-    Ok(())
+pub async fn set_audio_source(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    value: String,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::parse("audio_source", &value)?).await
 }
 
 /// Set RGB LED
 #[command]
-pub fn set_rgb_led(monitor_id: String, enabled: bool) -> Result<(), MsigdError> {
-    let value = if enabled { "on" } else { "off" };
-    MsigdExecutor::set_enum(&monitor_id, "rgb_led", value)?;
-    Ok(())
+pub async fn set_rgb_led(
+    backend: State<'_, SharedBackend>,
+    monitor_id: String,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    set_setting(backend, monitor_id, SettingValue::RgbLed(enabled)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::identity::StableIds;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::mystic::MysticLightMode;
+    use crate::mystic_light::MYSTIC_LIGHT_FILE;
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn test_settings_by_index_include_last_known_mystic_light() {
+        let dir = TempDir::new("monitor-settings");
+        let store = MysticLightStore::load(dir.join(MYSTIC_LIGHT_FILE));
+        let config = MysticLightConfig {
+            led_group: "0".to_string(),
+            mode: MysticLightMode::Static,
+            colors: vec!["#ff0000".to_string()],
+        };
+        store.record("SN1", config.clone()).unwrap();
+        let mock = Arc::new(MockBackend::new(vec![MockMonitor::new("SN1", "MAG274QRF-QD")]));
+        let backend: SharedBackend = Arc::new(StableIds::new(mock));
+
+        for id in ["1", "SN1"] {
+            let settings = query_settings(&backend, &store, id, false).await.unwrap();
+            assert_eq!(settings.last_known_mystic_light.unwrap().config, config, "{}", id);
+        }
+        assert!(matches!(
+            query_settings(&backend, &store, "2", false).await,
+            Err(MsigdError::MonitorNotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,134 @@
+//! Profile-related Tauri commands
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::commands::monitor::find_monitor;
+use crate::error::MsigdError;
+use crate::msigd::{FieldResult, MsigdParser, SharedBackend};
+use crate::mystic_light::MysticLightStore;
+use crate::profiles::file::{FileFormat, ProfileFile};
+use crate::profiles::{Profile, ProfileStore};
+
+/// Result of importing a profile file
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub profile: Profile,
+    pub warnings: Vec<String>,
+}
+
+/// List all saved profiles
+#[command]
+pub fn list_profiles(profiles: State<'_, ProfileStore>) -> Vec<Profile> {
+    profiles.list()
+}
+
+/// Save a monitor's current settings as a named profile
+///
+/// Mystic Light is not queryable, so the last config written to the monitor
+/// is saved with it.
+#[command]
+pub async fn save_profile_from_monitor(
+    backend: State<'_, SharedBackend>,
+    profiles: State<'_, ProfileStore>,
+    mystic_light: State<'_, Arc<MysticLightStore>>,
+    monitor_id: String,
+    name: String,
+) -> Result<Profile, MsigdError> {
+    if name.trim().is_empty() {
+        return Err(MsigdError::InvalidValue(
+            "Profile name must not be empty".to_string(),
+        ));
+    }
+    let monitor = find_monitor(&backend, &monitor_id).await?;
+    let mystic_light = mystic_light.get(&monitor.id).map(|last| last.config);
+    let output = backend
+        .query(&monitor_id)
+        .await
+        .map_err(|e| e.with_monitor(&monitor_id))?;
+    let queried = MsigdParser::parse_query(&output)?;
+    let profile = Profile::from_settings(name.trim(), &queried, mystic_light);
+    profiles.save(profile.clone())?;
+    Ok(profile)
+}
+
+/// Apply a saved profile, reporting which fields the monitor rejected
+#[command]
+pub async fn apply_profile(
+    backend: State<'_, SharedBackend>,
+    profiles: State<'_, ProfileStore>,
+    monitor_id: String,
+    name: String,
+) -> Result<Vec<FieldResult>, MsigdError> {
+    let profile = profiles.get(&name)?;
+    Ok(profile.apply(backend.as_ref(), &monitor_id).await)
+}
+
+/// Delete a saved profile
+#[command]
+pub fn delete_profile(profiles: State<'_, ProfileStore>, name: String) -> Result<(), MsigdError> {
+    profiles.delete(&name)
+}
+
+/// Export a saved profile to a TOML or JSON file
+///
+/// When `monitor_id` is given, its model and serial are recorded in the file.
+#[command]
+pub async fn export_profile(
+    backend: State<'_, SharedBackend>,
+    profiles: State<'_, ProfileStore>,
+    name: String,
+    path: String,
+    monitor_id: Option<String>,
+) -> Result<(), MsigdError> {
+    let path = PathBuf::from(path);
+    let format = FileFormat::from_path(&path)?;
+    let profile = profiles.get(&name)?;
+    let monitor = match monitor_id {
+        Some(id) => Some(find_monitor(&backend, &id).await?),
+        None => None,
+    };
+
+    let contents = ProfileFile::new(profile, monitor.as_ref()).to_string(format)?;
+    fs::write(&path, contents).map_err(|e| MsigdError::StorageError(e.to_string()))
+}
+
+/// Import a profile file into the saved profiles
+///
+/// Warns when `monitor_id` refers to a different model than the profile's.
+/// A saved profile with the same name is only replaced with `overwrite`.
+#[command]
+pub async fn import_profile(
+    backend: State<'_, SharedBackend>,
+    profiles: State<'_, ProfileStore>,
+    path: String,
+    monitor_id: Option<String>,
+    overwrite: bool,
+) -> Result<ImportResult, MsigdError> {
+    let path = PathBuf::from(path);
+    let format = FileFormat::from_path(&path)?;
+    let contents =
+        fs::read_to_string(&path).map_err(|e| MsigdError::StorageError(e.to_string()))?;
+    let file = ProfileFile::parse(&contents, format)?;
+
+    let mut warnings = Vec::new();
+    if let Some(id) = monitor_id {
+        let monitor = find_monitor(&backend, &id).await?;
+        warnings.extend(file.model_warning(&monitor));
+    }
+
+    if overwrite {
+        profiles.save(file.profile.clone())?;
+    } else {
+        profiles.add(file.profile.clone())?;
+    }
+    Ok(ImportResult {
+        profile: file.profile,
+        warnings,
+    })
+}
@@ -0,0 +1,29 @@
+//! Restore-on-reconnect Tauri commands
+
+use std::sync::Arc;
+
+use tauri::{command, State};
+
+use crate::error::MsigdError;
+use crate::restore::{RestoreReport, RestoreStore};
+
+/// Whether settings are re-applied when a monitor reconnects
+#[command]
+pub fn get_restore_on_reconnect(restore: State<'_, Arc<RestoreStore>>) -> bool {
+    restore.enabled()
+}
+
+/// Turn restore on reconnect on or off
+#[command]
+pub fn set_restore_on_reconnect(
+    restore: State<'_, Arc<RestoreStore>>,
+    enabled: bool,
+) -> Result<(), MsigdError> {
+    restore.set_enabled(enabled)
+}
+
+/// What was restored or failed on recent reconnects, newest first
+#[command]
+pub fn get_restore_log(restore: State<'_, Arc<RestoreStore>>) -> Vec<RestoreReport> {
+    restore.log()
+}
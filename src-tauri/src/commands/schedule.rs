@@ -0,0 +1,32 @@
+//! Schedule-related Tauri commands
+
+use std::sync::Arc;
+
+use tauri::{command, State};
+
+use crate::error::MsigdError;
+use crate::schedule::{ScheduleRule, ScheduleStore};
+
+/// List all schedule rules
+#[command]
+pub fn list_schedule_rules(schedules: State<'_, Arc<ScheduleStore>>) -> Vec<ScheduleRule> {
+    schedules.list()
+}
+
+/// Save a rule, replacing any existing rule with the same name
+#[command]
+pub fn save_schedule_rule(
+    schedules: State<'_, Arc<ScheduleStore>>,
+    rule: ScheduleRule,
+) -> Result<(), MsigdError> {
+    schedules.save(rule)
+}
+
+/// Delete a rule by name
+#[command]
+pub fn delete_schedule_rule(
+    schedules: State<'_, Arc<ScheduleStore>>,
+    name: String,
+) -> Result<(), MsigdError> {
+    schedules.delete(&name)
+}
@@ -0,0 +1,397 @@
+//! Diagnostics for hidraw access problems
+//!
+//! msigd talks to monitors through `/dev/hidraw*`, which is root-only unless a
+//! udev rule grants access. This module inspects each monitor's device node,
+//! the current user's groups and the installed udev rules, and can install
+//! the rule through `pkexec`.
+
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::error::MsigdError;
+use crate::msigd::Monitor;
+
+/// USB (vendor, product) IDs of the MSI monitor HID controllers msigd drives
+///
+/// Used for the udev rule, the rule check below and hotplug detection, so a
+/// new controller only needs adding here.
+pub const MSI_HID_IDS: &[(&str, &str)] = &[("1462", "3fa4")];
+/// Where the rule is installed; matches the file shipped with msigd
+pub const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/99-msigd.rules";
+
+/// Time allowed for `getfacl` on one device node
+const GETFACL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Rule giving the logged-in user access to the monitors' hidraw nodes
+pub fn udev_rule() -> String {
+    MSI_HID_IDS
+        .iter()
+        .map(|(vendor, product)| {
+            format!(
+                "SUBSYSTEM==\"hidraw\", ATTRS{{idVendor}}==\"{}\", ATTRS{{idProduct}}==\"{}\", \
+                 MODE=\"0660\", TAG+=\"uaccess\"\n",
+                vendor, product
+            )
+        })
+        .collect()
+}
+
+/// Whether a uevent `HID_ID` such as "0003:00001462:00003FA4" is an MSI monitor
+pub fn is_msi_hid_id(hid_id: &str) -> bool {
+    let mut fields = hid_id.trim().split(':').skip(1);
+    let (Some(vendor), Some(product)) = (fields.next(), fields.next()) else {
+        return false;
+    };
+    let matches = |field: &str, id: &str| {
+        u32::from_str_radix(field, 16).ok() == u32::from_str_radix(id, 16).ok()
+    };
+    MSI_HID_IDS
+        .iter()
+        .any(|(v, p)| matches(vendor, v) && matches(product, p))
+}
+
+/// Access details for one monitor's device node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceReport {
+    pub monitor_id: String,
+    pub path: String,
+    pub exists: bool,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// Permission bits in octal, e.g. "0660"
+    pub mode: Option<String>,
+    /// Extra ACL entries from `getfacl`, if available
+    pub acl: Vec<String>,
+    /// Whether the current user belongs to the node's group
+    pub in_group: bool,
+    /// Whether the node could be opened for reading and writing
+    pub accessible: bool,
+    pub error: Option<String>,
+}
+
+/// Structured result of `diagnose`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticReport {
+    pub msigd_installed: bool,
+    pub uid: Option<u32>,
+    pub groups: Vec<String>,
+    pub devices: Vec<DeviceReport>,
+    /// Rule file that already grants access, if any
+    pub udev_rule: Option<String>,
+    /// Whether `install_udev_rule` can elevate via pkexec
+    pub can_install_rule: bool,
+    /// Human-readable hints, most important first
+    pub suggestions: Vec<String>,
+}
+
+/// File locations inspected by the diagnostics, replaceable in tests
+#[derive(Debug, Clone)]
+pub struct SystemPaths {
+    pub proc_status: PathBuf,
+    pub group_file: PathBuf,
+    pub passwd_file: PathBuf,
+    pub rules_dirs: Vec<PathBuf>,
+}
+
+impl Default for SystemPaths {
+    fn default() -> Self {
+        Self {
+            proc_status: PathBuf::from("/proc/self/status"),
+            group_file: PathBuf::from("/etc/group"),
+            passwd_file: PathBuf::from("/etc/passwd"),
+            rules_dirs: vec![
+                PathBuf::from("/etc/udev/rules.d"),
+                PathBuf::from("/usr/lib/udev/rules.d"),
+                PathBuf::from("/lib/udev/rules.d"),
+            ],
+        }
+    }
+}
+
+impl DiagnosticReport {
+    /// Inspect the monitors' device nodes and the udev setup
+    pub async fn collect(paths: &SystemPaths, msigd_installed: bool, monitors: &[Monitor]) -> Self {
+        let status = fs::read_to_string(&paths.proc_status).unwrap_or_default();
+        let uid = status_ids(&status, "Uid:").first().copied();
+        let gids = status_ids(&status, "Groups:");
+        let group_names = id_names(&paths.group_file);
+        let user_names = id_names(&paths.passwd_file);
+        let name = |names: &[(u32, String)], id: u32| {
+            names
+                .iter()
+                .find(|(i, _)| *i == id)
+                .map(|(_, n)| n.clone())
+                .unwrap_or_else(|| id.to_string())
+        };
+
+        let mut devices = Vec::new();
+        for (monitor, path) in monitors
+            .iter()
+            .filter_map(|m| Some((m, m.device_path.as_deref()?)))
+        {
+            let mut report = DeviceReport {
+                monitor_id: monitor.id.clone(),
+                path: path.to_string(),
+                exists: false,
+                owner: None,
+                group: None,
+                mode: None,
+                acl: Vec::new(),
+                in_group: false,
+                accessible: false,
+                error: None,
+            };
+            match fs::metadata(path) {
+                Ok(meta) => {
+                    report.exists = true;
+                    report.owner = Some(name(&user_names, meta.uid()));
+                    report.group = Some(name(&group_names, meta.gid()));
+                    report.mode = Some(format!("{:04o}", meta.permissions().mode() & 0o7777));
+                    report.in_group = gids.contains(&meta.gid());
+                    report.acl = acl_entries(Path::new(path)).await;
+                    match OpenOptions::new().read(true).write(true).open(path) {
+                        Ok(_) => report.accessible = true,
+                        Err(e) => report.error = Some(e.to_string()),
+                    }
+                }
+                Err(e) => report.error = Some(e.to_string()),
+            }
+            devices.push(report);
+        }
+
+        let udev_rule = find_udev_rule(&paths.rules_dirs);
+        let can_install_rule = which("pkexec");
+        let suggestions = suggestions(msigd_installed, &devices, udev_rule.is_some());
+
+        Self {
+            msigd_installed,
+            uid,
+            groups: gids.iter().map(|g| name(&group_names, *g)).collect(),
+            devices,
+            udev_rule,
+            can_install_rule,
+            suggestions,
+        }
+    }
+}
+
+fn suggestions(msigd_installed: bool, devices: &[DeviceReport], has_rule: bool) -> Vec<String> {
+    let mut hints = Vec::new();
+    if !msigd_installed {
+        hints.push("Install msigd from https://github.com/couriersud/msigd".to_string());
+    }
+    let blocked: Vec<&str> = devices
+        .iter()
+        .filter(|d| d.exists && !d.accessible)
+        .map(|d| d.path.as_str())
+        .collect();
+    if !blocked.is_empty() {
+        if has_rule {
+            hints.push(format!(
+                "A udev rule is installed but {} is still not accessible; \
+                 replug the monitor's USB cable or log out and back in",
+                blocked.join(", ")
+            ));
+        } else {
+            hints.push(format!(
+                "No udev rule grants access to {}; install the rule to use msigd without root",
+                blocked.join(", ")
+            ));
+        }
+    }
+    hints
+}
+
+/// Numeric ids from a `/proc/self/status` line such as "Groups: 4 24 1000"
+fn status_ids(status: &str, key: &str) -> Vec<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .map(|rest| rest.split_whitespace().filter_map(|id| id.parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// (id, name) pairs from an `/etc/group` or `/etc/passwd` style file
+fn id_names(path: &Path) -> Vec<(u32, String)> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/// Named user/group ACL entries, empty if `getfacl` is unavailable or hangs
+async fn acl_entries(path: &Path) -> Vec<String> {
+    let child = Command::new("getfacl")
+        .args(["--omit-header", "--absolute-names"])
+        .arg(path)
+        .kill_on_drop(true)
+        .output();
+    let Ok(Ok(output)) = tokio::time::timeout(GETFACL_TIMEOUT, child).await else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| {
+            // Base entries look like "user::rw-"; named ones carry a name
+            (line.starts_with("user:") || line.starts_with("group:")) && !line.contains("::")
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// First rules file mentioning the vendor and product ID of an MSI monitor
+fn find_udev_rule(dirs: &[PathBuf]) -> Option<String> {
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "rules"))
+        .find(|path| {
+            fs::read_to_string(path).is_ok_and(|rules| {
+                let rules = rules.to_lowercase();
+                MSI_HID_IDS
+                    .iter()
+                    .any(|(vendor, product)| rules.contains(vendor) && rules.contains(product))
+            })
+        })
+        .map(|path| path.display().to_string())
+}
+
+fn which(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| dir.join(program).is_file())
+    })
+}
+
+/// Write the udev rule and reload udev in a single pkexec prompt
+pub async fn install_udev_rule() -> Result<(), MsigdError> {
+    let script = "printf '%s' \"$1\" > \"$2\" \
+                  && udevadm control --reload-rules \
+                  && udevadm trigger --subsystem-match=hidraw";
+    let output = tokio::process::Command::new("pkexec")
+        .args(["sh", "-c", script, "sh", &udev_rule(), UDEV_RULE_PATH])
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                MsigdError::ShellError("pkexec is not installed".to_string())
+            }
+            _ => MsigdError::ShellError(e.to_string()),
+        })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // pkexec: 126 = authorization dismissed or denied, 127 = not authorized
+        Some(126 | 127) => Err(MsigdError::PermissionDenied { stderr }),
+        code => Err(MsigdError::CommandFailed {
+            stderr,
+            exit_status: code,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn monitor(id: &str, path: &Path) -> Monitor {
+        Monitor {
+            id: id.to_string(),
+            index: id.to_string(),
+            name: None,
+            serial: "SN1".to_string(),
+            vendor: "MS".to_string(),
+            model: "MAG274QRF-QD".to_string(),
+            firmware: "Unknown".to_string(),
+            device_path: Some(path.display().to_string()),
+            identity: "SN1".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_reports_devices_groups_and_rules() {
+        let dir = TempDir::new("diagnose");
+        let root = dir.path();
+        let device = root.join("hidraw3");
+        fs::write(&device, "").unwrap();
+        fs::set_permissions(&device, fs::Permissions::from_mode(0o640)).unwrap();
+        let gid = fs::metadata(&device).unwrap().gid();
+
+        fs::write(root.join("status"), format!("Name:\tx\nUid:\t1000\t1000\nGroups:\t{} 24\n", gid))
+            .unwrap();
+        fs::write(root.join("group"), format!("hidusers:x:{}:\ncdrom:x:24:\n", gid)).unwrap();
+        fs::write(root.join("passwd"), "").unwrap();
+        let rules = root.join("rules.d");
+        fs::create_dir(&rules).unwrap();
+        fs::write(rules.join("50-other.rules"), "SUBSYSTEM==\"usb\"\n").unwrap();
+
+        let paths = SystemPaths {
+            proc_status: root.join("status"),
+            group_file: root.join("group"),
+            passwd_file: root.join("passwd"),
+            rules_dirs: vec![rules.clone()],
+        };
+        let missing = root.join("hidraw9");
+        let monitors = [monitor("1", &device), monitor("2", &missing)];
+
+        let report = DiagnosticReport::collect(&paths, true, &monitors).await;
+        assert_eq!(report.uid, Some(1000));
+        assert_eq!(report.groups, ["hidusers", "cdrom"]);
+        assert!(report.udev_rule.is_none());
+        assert_eq!(report.devices[0].mode.as_deref(), Some("0640"));
+        assert_eq!(report.devices[0].group.as_deref(), Some("hidusers"));
+        assert!(report.devices[0].in_group);
+        assert!(!report.devices[1].exists);
+
+        fs::write(rules.join("99-msigd.rules"), udev_rule()).unwrap();
+        let report = DiagnosticReport::collect(&paths, false, &monitors).await;
+        assert!(report.udev_rule.unwrap().ends_with("99-msigd.rules"));
+        assert!(report.suggestions[0].contains("Install msigd"));
+    }
+
+    #[test]
+    fn test_matches_msi_hid_ids() {
+        assert!(is_msi_hid_id("0003:00001462:00003FA4"));
+        assert!(is_msi_hid_id("0003:00001462:00003fa4\n"));
+        assert!(!is_msi_hid_id("0003:00001462:00003FA5"));
+        assert!(!is_msi_hid_id("0003:0000046D:0000C52B"));
+        assert!(!is_msi_hid_id("garbage"));
+    }
+
+    #[test]
+    fn test_suggests_rule_for_blocked_device() {
+        let device = DeviceReport {
+            monitor_id: "1".to_string(),
+            path: "/dev/hidraw3".to_string(),
+            exists: true,
+            owner: Some("root".to_string()),
+            group: Some("root".to_string()),
+            mode: Some("0600".to_string()),
+            acl: Vec::new(),
+            in_group: false,
+            accessible: false,
+            error: Some("Permission denied (os error 13)".to_string()),
+        };
+
+        let hints = suggestions(true, &[device], false);
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0].contains("install the rule"));
+    }
+}
@@ -7,11 +7,23 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 #[allow(dead_code)]
 pub enum MsigdError {
+    #[error("msigd is not installed or not in PATH")]
+    NotInstalled,
+
+    #[error("Permission denied accessing the monitor: {stderr}")]
+    PermissionDenied { stderr: String },
+
     #[error("Failed to execute msigd: {0}")]
     ExecutionFailed(String),
 
-    #[error("Command failed: {0}")]
-    CommandFailed(String),
+    #[error("msigd timed out after {0} ms")]
+    Timeout(u64),
+
+    #[error("Command failed: {stderr}")]
+    CommandFailed {
+        stderr: String,
+        exit_status: Option<i32>,
+    },
 
     #[error("Parse error: {0}")]
     ParseError(String),
@@ -22,8 +34,127 @@ pub enum MsigdError {
     #[error("Monitor not found: {0}")]
     MonitorNotFound(String),
 
+    #[error("{0} is not supported by this monitor")]
+    UnsupportedSetting(String),
+
     #[error("Shell error: {0}")]
     ShellError(String),
+
+    #[error("Profile not found: {0}")]
+    ProfileNotFound(String),
+
+    #[error("A profile named {0} already exists")]
+    ProfileExists(String),
+
+    #[error("Schedule rule not found: {0}")]
+    RuleNotFound(String),
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    #[error("Light sensor error: {0}")]
+    SensorError(String),
+
+    #[error("Verification failed: wrote {expected} but the monitor reports {actual}")]
+    VerifyFailed { expected: String, actual: String },
+
+    /// Another error with the monitor and setting it happened on
+    #[error("{source}")]
+    Context {
+        source: Box<MsigdError>,
+        monitor_id: Option<String>,
+        setting: Option<String>,
+    },
+}
+
+impl MsigdError {
+    /// Stable identifier the frontend can match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotInstalled => "not_installed",
+            Self::PermissionDenied { .. } => "permission_denied",
+            Self::ExecutionFailed(_) => "execution_failed",
+            Self::Timeout(_) => "timeout",
+            Self::CommandFailed { .. } => "command_failed",
+            Self::ParseError(_) => "parse_error",
+            Self::InvalidValue(_) => "invalid_value",
+            Self::MonitorNotFound(_) => "monitor_not_found",
+            Self::UnsupportedSetting(_) => "unsupported_setting",
+            Self::ShellError(_) => "shell_error",
+            Self::ProfileNotFound(_) => "profile_not_found",
+            Self::ProfileExists(_) => "profile_exists",
+            Self::RuleNotFound(_) => "rule_not_found",
+            Self::StorageError(_) => "storage_error",
+            Self::SensorError(_) => "sensor_error",
+            Self::VerifyFailed { .. } => "verify_failed",
+            Self::Context { source, .. } => source.code(),
+        }
+    }
+
+    /// The msigd setting the error happened on, if known
+    pub fn setting(&self) -> Option<&str> {
+        match self {
+            Self::Context { setting, .. } => setting.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The error without any attached context
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Record the monitor the error happened on
+    pub fn with_monitor(self, monitor_id: &str) -> Self {
+        self.with_context(Some(monitor_id), None)
+    }
+
+    /// Record the msigd setting the error happened on
+    pub fn with_setting(self, setting: &str) -> Self {
+        self.with_context(None, Some(setting))
+    }
+
+    fn with_context(self, monitor: Option<&str>, setting_name: Option<&str>) -> Self {
+        match self {
+            Self::Context {
+                source,
+                monitor_id,
+                setting,
+            } => Self::Context {
+                source,
+                monitor_id: monitor_id.or(monitor.map(str::to_string)),
+                setting: setting.or(setting_name.map(str::to_string)),
+            },
+            other => Self::Context {
+                source: Box::new(other),
+                monitor_id: monitor.map(str::to_string),
+                setting: setting_name.map(str::to_string),
+            },
+        }
+    }
+}
+
+/// Serialized form of an error sent to the frontend
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorBody<'a> {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    monitor_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    setting: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_status: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<&'a str>,
 }
 
 // Tauri 2.0 requires serializable errors for commands
@@ -32,6 +163,81 @@ impl Serialize for MsigdError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        let (monitor_id, setting) = match self {
+            Self::Context {
+                monitor_id,
+                setting,
+                ..
+            } => (monitor_id.as_deref(), setting.as_deref()),
+            Self::MonitorNotFound(id) => (Some(id.as_str()), None),
+            _ => (None, None),
+        };
+        let (stderr, exit_status) = match self.root() {
+            Self::PermissionDenied { stderr } => (Some(stderr.as_str()), None),
+            Self::CommandFailed {
+                stderr,
+                exit_status,
+            } => (Some(stderr.as_str()), *exit_status),
+            _ => (None, None),
+        };
+        let (expected, actual) = match self.root() {
+            Self::VerifyFailed { expected, actual } => {
+                (Some(expected.as_str()), Some(actual.as_str()))
+            }
+            _ => (None, None),
+        };
+
+        ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            monitor_id,
+            setting,
+            stderr,
+            exit_status,
+            expected,
+            actual,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serializes_code_and_context() {
+        let error = MsigdError::CommandFailed {
+            stderr: "hid write failed".to_string(),
+            exit_status: Some(1),
+        }
+        .with_setting("brightness")
+        .with_monitor("2");
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "command_failed",
+                "message": "Command failed: hid write failed",
+                "monitorId": "2",
+                "setting": "brightness",
+                "stderr": "hid write failed",
+                "exitStatus": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_serializes_plain_error() {
+        let error = MsigdError::InvalidValue("Brightness must be 0-100".to_string());
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "invalid_value",
+                "message": "Invalid value: Brightness must be 0-100",
+            })
+        );
     }
 }
@@ -0,0 +1,253 @@
+//! Monitor hotplug detection
+//!
+//! Polls `/sys/class/hidraw` for MSI HID nodes appearing or disappearing.
+//! When the set changes the monitor list is re-run and diffed against the
+//! known monitors by stable id, and `monitor-added` / `monitor-removed`
+//! events are emitted to the frontend. Cached settings of every monitor are
+//! dropped too, since a monitor swapped for the same model would otherwise
+//! be served the old one's settings. Reconnected monitors get their last
+//! settings back when restore on reconnect is enabled.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::diagnostics;
+use crate::error::MsigdError;
+use crate::msigd::backend::{self, MonitorBackend};
+use crate::msigd::{Monitor, SharedBackend};
+use crate::restore::{RestoreReport, RestoreStore};
+
+/// Event emitted with the `Monitor` that was connected
+pub const MONITOR_ADDED: &str = "monitor-added";
+/// Event emitted with the `Monitor` that was disconnected
+pub const MONITOR_REMOVED: &str = "monitor-removed";
+/// Event emitted with the `RestoreReport` after settings were re-applied
+pub const MONITOR_RESTORED: &str = "monitor-restored";
+
+/// How often `/sys/class/hidraw` is scanned
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A change in the set of connected monitors
+#[derive(Debug, Clone)]
+pub enum HotplugEvent {
+    Added(Monitor),
+    Removed(Monitor),
+}
+
+impl HotplugEvent {
+    /// Tauri event name for this change
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Added(_) => MONITOR_ADDED,
+            Self::Removed(_) => MONITOR_REMOVED,
+        }
+    }
+
+    pub fn monitor(&self) -> &Monitor {
+        match self {
+            Self::Added(monitor) | Self::Removed(monitor) => monitor,
+        }
+    }
+}
+
+/// hidraw nodes under `sysfs_root` that belong to MSI monitors
+pub fn msi_hidraw_nodes(sysfs_root: &Path) -> BTreeSet<String> {
+    let Ok(entries) = fs::read_dir(sysfs_root.join("class/hidraw")) else {
+        return BTreeSet::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            fs::read_to_string(entry.path().join("device/uevent"))
+                .map(|uevent| {
+                    uevent
+                        .lines()
+                        .filter_map(|line| line.strip_prefix("HID_ID="))
+                        .any(diagnostics::is_msi_hid_id)
+                })
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Tracks connected monitors and reports what changed between polls
+pub struct HotplugWatcher {
+    sysfs_root: PathBuf,
+    nodes: BTreeSet<String>,
+    monitors: Vec<Monitor>,
+}
+
+impl HotplugWatcher {
+    /// Record the monitors connected now, without reporting them as added
+    pub async fn start(
+        sysfs_root: PathBuf,
+        backend: &dyn MonitorBackend,
+    ) -> Result<Self, MsigdError> {
+        let nodes = msi_hidraw_nodes(&sysfs_root);
+        let monitors = backend::monitors(backend).await?;
+        Ok(Self {
+            sysfs_root,
+            nodes,
+            monitors,
+        })
+    }
+
+    /// Scan for hidraw changes and diff the monitor list if there were any
+    ///
+    /// A failed list is returned as an error and retried on the next poll.
+    pub async fn poll(
+        &mut self,
+        backend: &dyn MonitorBackend,
+    ) -> Result<Vec<HotplugEvent>, MsigdError> {
+        let nodes = msi_hidraw_nodes(&self.sysfs_root);
+        if nodes == self.nodes {
+            return Ok(Vec::new());
+        }
+
+        // Query output cached before the change may describe another monitor
+        for monitor in &self.monitors {
+            backend.invalidate(&monitor.id).await;
+        }
+        let monitors = backend::monitors(backend).await?;
+        for monitor in &monitors {
+            backend.invalidate(&monitor.id).await;
+        }
+        let known = |list: &[Monitor], monitor: &Monitor| list.iter().any(|m| m.id == monitor.id);
+        let mut events: Vec<HotplugEvent> = self
+            .monitors
+            .iter()
+            .filter(|m| !known(&monitors, m))
+            .map(|m| HotplugEvent::Removed(m.clone()))
+            .collect();
+        events.extend(
+            monitors
+                .iter()
+                .filter(|m| !known(&self.monitors, m))
+                .map(|m| HotplugEvent::Added(m.clone())),
+        );
+
+        self.nodes = nodes;
+        self.monitors = monitors;
+        Ok(events)
+    }
+}
+
+/// Watch for monitors being connected or disconnected in the background
+pub fn spawn(app: AppHandle, backend: SharedBackend, restore: Arc<RestoreStore>) {
+    tauri::async_runtime::spawn(async move {
+        let mut watcher = match HotplugWatcher::start(PathBuf::from("/sys"), backend.as_ref()).await
+        {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("Hotplug detection disabled: {}", e);
+                return;
+            }
+        };
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            match watcher.poll(backend.as_ref()).await {
+                Ok(events) => {
+                    for event in events {
+                        let _ = app.emit(event.name(), event.monitor().clone());
+                        if let HotplugEvent::Added(monitor) = &event {
+                            if let Some(report) = restore.restore(backend.as_ref(), monitor).await {
+                                log_restore(&report);
+                                let _ = app.emit(MONITOR_RESTORED, report);
+                            }
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to refresh monitors after hotplug: {}", e),
+            }
+        }
+    });
+}
+
+fn log_restore(report: &RestoreReport) {
+    for result in &report.results {
+        match &result.error {
+            None => eprintln!("Restored {} on {}", result.field, report.monitor_id),
+            Some(e) => eprintln!(
+                "Failed to restore {} on {}: {}",
+                result.field, report.monitor_id, e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::cache::{QueryCache, DEFAULT_TTL};
+    use crate::msigd::identity::StableIds;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::MsigdParser;
+    use crate::test_support::FakeSysfs;
+
+    #[test]
+    fn test_only_msi_nodes_are_tracked() {
+        let sysfs = FakeSysfs::new("hidraw-nodes");
+        sysfs.add_hidraw("hidraw0", "0003:00001462:00003FA4");
+        sysfs.add_hidraw("hidraw1", "0003:0000046D:0000C52B");
+
+        assert_eq!(msi_hidraw_nodes(sysfs.root()), BTreeSet::from(["hidraw0".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_poll_reports_added_and_removed_monitors() {
+        let sysfs = FakeSysfs::new("hotplug");
+        sysfs.add_hidraw("hidraw0", "0003:00001462:00003FA4");
+        sysfs.add_hidraw("hidraw1", "0003:00001462:00003FA4");
+        let backend = MockBackend::new(vec![
+            MockMonitor::new("SN1", "MAG274QRF-QD"),
+            MockMonitor::new("SN2", "MAG321CURV"),
+        ]);
+        let mut watcher =
+            HotplugWatcher::start(sysfs.root().to_path_buf(), &backend).await.unwrap();
+        assert!(watcher.poll(&backend).await.unwrap().is_empty());
+
+        sysfs.remove_hidraw("hidraw0");
+        backend.unplug("1");
+        let events = watcher.poll(&backend).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), MONITOR_REMOVED);
+        assert_eq!(events[0].monitor().id, "SN1");
+
+        sysfs.add_hidraw("hidraw2", "0003:00001462:00003FA4");
+        backend.plug(MockMonitor::new("SN3", "MPG321UR-QD"));
+        let events = watcher.poll(&backend).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), MONITOR_ADDED);
+        assert_eq!(events[0].monitor().id, "SN3");
+        assert_eq!(watcher.monitors.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hidraw_change_drops_cached_settings() {
+        let sysfs = FakeSysfs::new("hotplug-cache");
+        sysfs.add_hidraw("hidraw0", "0003:00001462:00003FA4");
+        let mock = Arc::new(MockBackend::new(vec![MockMonitor::new("SN1", "MAG274QRF-QD")]));
+        let backend = StableIds::new(Arc::new(QueryCache::new(mock.clone(), DEFAULT_TTL)));
+        let mut watcher =
+            HotplugWatcher::start(sysfs.root().to_path_buf(), &backend).await.unwrap();
+        let brightness = |output: String| MsigdParser::parse_settings(&output).unwrap().brightness;
+        let before = brightness(backend.query("SN1").await.unwrap());
+
+        // Swapped for the same model, so the monitor list reads the same
+        sysfs.remove_hidraw("hidraw0");
+        sysfs.add_hidraw("hidraw1", "0003:00001462:00003FA4");
+        mock.unplug("1");
+        let mut replacement = MockMonitor::new("SN1", "MAG274QRF-QD");
+        replacement.settings.brightness = before - 10;
+        mock.plug(replacement);
+        assert!(watcher.poll(&backend).await.unwrap().is_empty());
+
+        assert_eq!(brightness(backend.query("SN1").await.unwrap()), before - 10);
+    }
+}
@@ -1,10 +1,50 @@
 //! MSI Gaming Device GUI - Tauri backend
 
+mod ambient;
+mod app_profiles;
+mod circadian;
+mod cli;
 mod commands;
+mod diagnostics;
 mod error;
+mod hotplug;
 mod msigd;
+mod mystic_light;
+mod names;
+mod profiles;
+mod restore;
+mod schedule;
+mod solar;
+#[cfg(test)]
+mod test_support;
+mod transition;
 
-use commands::monitor;
+use std::sync::Arc;
+
+use tauri::Manager;
+
+use ambient::{AmbientStore, AMBIENT_FILE};
+use app_profiles::{AppRuleStore, APP_RULES_FILE};
+use circadian::{CircadianStore, CIRCADIAN_FILE};
+use commands::{
+    ambient as ambient_commands, app_profiles as app_profile_commands,
+    circadian as circadian_commands, diagnostics as diagnostic_commands, monitor,
+    profiles as profile_commands, restore as restore_commands, schedule as schedule_commands,
+};
+use msigd::SharedBackend;
+use mystic_light::{MysticLightRecorder, MysticLightStore, MYSTIC_LIGHT_FILE};
+use names::{MonitorNames, NAMES_FILE};
+use profiles::{ProfileStore, PROFILES_FILE};
+use restore::{RestoreRecorder, RestoreStore, RESTORE_FILE};
+use schedule::{ScheduleStore, SCHEDULES_FILE};
+use transition::Transitions;
+
+/// Run a headless CLI subcommand, returning the process exit code
+///
+/// Returns `None` when no subcommand was given and the GUI should start.
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    cli::run(args)
+}
 
 /// Run the Tauri application
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -18,11 +58,67 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::new().build())
+        .setup(|app| {
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(ProfileStore::load(config_dir.join(PROFILES_FILE)));
+            app.manage(MonitorNames::load(config_dir.join(NAMES_FILE)));
+            let mystic_light = Arc::new(MysticLightStore::load(config_dir.join(MYSTIC_LIGHT_FILE)));
+            app.manage(mystic_light.clone());
+
+            // Real msigd by default, in-memory mock with MSIGD_GUI_BACKEND=mock;
+            // writes are remembered for restore on reconnect, Mystic Light
+            // writes as the last known LED config, and writes cancel any
+            // transition running on the same setting
+            let restore = Arc::new(RestoreStore::load(config_dir.join(RESTORE_FILE)));
+            let recorded = Arc::new(RestoreRecorder::new(
+                msigd::backend::from_env(),
+                restore.clone(),
+            ));
+            let transitions = Arc::new(Transitions::new(Arc::new(MysticLightRecorder::new(
+                recorded,
+                mystic_light,
+            ))));
+            let backend: SharedBackend = transitions.clone();
+            app.manage(backend.clone());
+            app.manage(transitions.clone());
+            app.manage(restore.clone());
+            restore::spawn_flush(restore.clone());
+
+            // Emit monitor-added/monitor-removed when monitors are plugged in or out
+            hotplug::spawn(app.handle().clone(), backend.clone(), restore);
+
+            // Run time-of-day rules even while the window is hidden
+            let schedules = Arc::new(ScheduleStore::load(config_dir.join(SCHEDULES_FILE)));
+            app.manage(schedules.clone());
+            schedule::spawn(app.handle().clone(), transitions, schedules);
+
+            // Switch profiles while matching applications run
+            let app_rules = Arc::new(AppRuleStore::load(config_dir.join(APP_RULES_FILE)));
+            app.manage(app_rules.clone());
+            app_profiles::spawn(app.handle().clone(), backend.clone(), app_rules);
+
+            // Follow sunrise and sunset when circadian mode is enabled
+            let circadian = Arc::new(CircadianStore::load(config_dir.join(CIRCADIAN_FILE)));
+            app.manage(circadian.clone());
+            circadian::spawn(backend.clone(), circadian);
+
+            // Follow the ambient light sensor when auto-brightness is enabled
+            let ambient = Arc::new(AmbientStore::load(config_dir.join(AMBIENT_FILE)));
+            app.manage(ambient.clone());
+            ambient::spawn(backend, ambient);
+            Ok(())
+        })
         // Register all command handlers
         .invoke_handler(tauri::generate_handler![
             // Monitor commands
             monitor::list_monitors,
+            monitor::rename_monitor,
             monitor::get_monitor_settings,
+            monitor::get_monitor_capabilities,
+            monitor::apply_settings,
+            monitor::get_setting_schema,
+            monitor::set_setting,
+            monitor::transition_setting,
             monitor::set_brightness,
             monitor::set_contrast,
             monitor::set_sharpness,
@@ -37,6 +133,7 @@ pub fn run() {
             monitor::set_hdcr,
             monitor::set_refresh_rate_display,
             // LED commands
+            monitor::get_mystic_light_modes,
             monitor::set_mystic_light,
             // Phase 1: OSD commands
             monitor::set_osd_transparency,
@@ -64,7 +161,45 @@ pub fn run() {
             monitor::set_kvm,
             monitor::set_audio_source,
             monitor::set_rgb_led,
+            // Profile commands
+            profile_commands::list_profiles,
+            profile_commands::save_profile_from_monitor,
+            profile_commands::apply_profile,
+            profile_commands::delete_profile,
+            profile_commands::export_profile,
+            profile_commands::import_profile,
+            // Restore commands
+            restore_commands::get_restore_on_reconnect,
+            restore_commands::set_restore_on_reconnect,
+            restore_commands::get_restore_log,
+            // Schedule commands
+            schedule_commands::list_schedule_rules,
+            schedule_commands::save_schedule_rule,
+            schedule_commands::delete_schedule_rule,
+            // App profile commands
+            app_profile_commands::list_app_rules,
+            app_profile_commands::save_app_rule,
+            app_profile_commands::delete_app_rule,
+            // Circadian commands
+            circadian_commands::get_circadian_config,
+            circadian_commands::set_circadian_config,
+            circadian_commands::get_sun_times,
+            // Auto-brightness commands
+            ambient_commands::get_ambient_config,
+            ambient_commands::set_ambient_config,
+            ambient_commands::list_light_sensors,
+            // Diagnostics commands
+            diagnostic_commands::diagnose,
+            diagnostic_commands::install_udev_rule,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Save settings remembered since the last periodic flush
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = app.state::<Arc<RestoreStore>>().flush() {
+                    eprintln!("Failed to save restore settings: {}", e);
+                }
+            }
+        });
 }
@@ -3,5 +3,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = msigd_gui_lib::run_cli(&args) {
+        std::process::exit(code);
+    }
     msigd_gui_lib::run();
 }
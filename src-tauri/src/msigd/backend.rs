@@ -0,0 +1,162 @@
+//! Pluggable backends for talking to monitors
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::error::MsigdError;
+use crate::msigd::cache::{self, QueryCache};
+use crate::msigd::executor::{MsigdExecutor, DEFAULT_TIMEOUT};
+use crate::msigd::identity::{self, StableIds};
+use crate::msigd::mock::MockBackend;
+use crate::msigd::parser::MsigdParser;
+use crate::msigd::queue::MonitorQueue;
+use crate::msigd::types::Monitor;
+use crate::msigd::verify::VerifiedWrites;
+
+/// Environment variable selecting the backend at startup ("msigd" or "mock")
+pub const BACKEND_ENV: &str = "MSIGD_GUI_BACKEND";
+
+/// Environment variable overriding the per-invocation msigd timeout
+pub const TIMEOUT_ENV: &str = "MSIGD_GUI_TIMEOUT_MS";
+
+/// Environment variable adding simulated latency to the mock backend
+pub const MOCK_LATENCY_ENV: &str = "MSIGD_GUI_MOCK_LATENCY_MS";
+
+/// Environment variable overriding how long query output is cached
+pub const CACHE_TTL_ENV: &str = "MSIGD_GUI_CACHE_TTL_MS";
+
+/// Environment variable enabling read-back verification of writes, with the
+/// number of retries for settings that did not stick (unset disables it)
+pub const VERIFY_RETRIES_ENV: &str = "MSIGD_GUI_VERIFY_RETRIES";
+
+/// Backend shared through Tauri managed state
+pub type SharedBackend = Arc<dyn MonitorBackend>;
+
+/// Low-level access to monitors
+///
+/// Methods return raw msigd-formatted output so the same parser is used
+/// regardless of whether the data came from real hardware or a mock.
+#[async_trait]
+pub trait MonitorBackend: Send + Sync {
+    /// List all connected monitors (`--list` output)
+    async fn list(&self) -> Result<String, MsigdError>;
+
+    /// Query settings for a specific monitor (`--query --numeric` output)
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError>;
+
+    /// Set a single setting using its msigd flag name and value
+    async fn set(&self, monitor_id: &str, setting: &str, value: &str) -> Result<String, MsigdError>;
+
+    /// Set several settings in one call, as (setting, value) pairs
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError>;
+
+    /// Set Mystic Light LED configuration
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError>;
+
+    /// Check if the backend is usable
+    async fn check_available(&self) -> Result<bool, MsigdError>;
+
+    /// Drop cached state for a monitor so the next query reaches the device
+    async fn invalidate(&self, _monitor_id: &str) {}
+}
+
+#[async_trait]
+impl MonitorBackend for MsigdExecutor {
+    async fn list(&self) -> Result<String, MsigdError> {
+        self.list_monitors().await
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.query_monitor(monitor_id).await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        self.set_enum(monitor_id, setting, value).await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        MsigdExecutor::set_many(self, monitor_id, settings).await
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.set_mystic_light(monitor_id, config).await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        MsigdExecutor::check_available(self).await
+    }
+}
+
+/// List connected monitors with stable ids
+pub async fn monitors(backend: &dyn MonitorBackend) -> Result<Vec<Monitor>, MsigdError> {
+    let mut monitors = MsigdParser::parse_monitor_list(&backend.list().await?)?;
+    identity::assign_ids(&mut monitors);
+    Ok(monitors)
+}
+
+/// List connected monitors with the firmware version from each one's query
+///
+/// `--list` does not report firmware; monitors whose query fails keep
+/// "Unknown" rather than failing the whole list.
+pub async fn list_monitors(backend: &dyn MonitorBackend) -> Result<Vec<Monitor>, MsigdError> {
+    let mut monitors = monitors(backend).await?;
+    for monitor in &mut monitors {
+        if let Ok(output) = backend.query(&monitor.index).await {
+            if let Some(firmware) = MsigdParser::parse_firmware(&output) {
+                monitor.firmware = firmware;
+            }
+        }
+    }
+    Ok(monitors)
+}
+
+/// Create the backend selected by `MSIGD_GUI_BACKEND` (defaults to msigd)
+///
+/// The backend is wrapped in a [`QueryCache`], in a [`MonitorQueue`] so HID
+/// access is serialized per monitor, and in [`StableIds`] so callers can use
+/// stable monitor ids.
+/// With `MSIGD_GUI_VERIFY_RETRIES` set, writes are read back and retried
+/// inside the queue so a verified write holds the monitor until it is done.
+pub fn from_env() -> SharedBackend {
+    let mut inner: SharedBackend = match std::env::var(BACKEND_ENV).as_deref() {
+        Ok("mock") => {
+            let latency = env_millis(MOCK_LATENCY_ENV).unwrap_or(Duration::ZERO);
+            Arc::new(MockBackend::default().with_latency(latency))
+        }
+        _ => {
+            let timeout = env_millis(TIMEOUT_ENV).unwrap_or(DEFAULT_TIMEOUT);
+            Arc::new(MsigdExecutor::new(timeout))
+        }
+    };
+    if let Some(retries) = std::env::var(VERIFY_RETRIES_ENV)
+        .ok()
+        .and_then(|r| r.parse().ok())
+    {
+        inner = Arc::new(VerifiedWrites::new(inner, retries));
+    }
+    let ttl = env_millis(CACHE_TTL_ENV).unwrap_or(cache::DEFAULT_TTL);
+    let cached = Arc::new(QueryCache::new(inner, ttl));
+    Arc::new(StableIds::new(Arc::new(MonitorQueue::new(cached))))
+}
+
+fn env_millis(name: &str) -> Option<Duration> {
+    std::env::var(name)
+        .ok()
+        .and_then(|ms| ms.parse().ok())
+        .map(Duration::from_millis)
+}
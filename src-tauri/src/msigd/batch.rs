@@ -0,0 +1,193 @@
+//! Batch writes of several settings in one msigd invocation
+
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::registry;
+use crate::msigd::types::*;
+
+/// A validated set of writes ready to be sent in a single call
+#[derive(Debug, Default)]
+pub struct SettingsBatch {
+    /// (field, msigd setting, value) for every valid field
+    entries: Vec<(&'static str, &'static str, String)>,
+    /// Fields that failed validation and will not be sent
+    rejected: Vec<FieldResult>,
+}
+
+impl SettingsBatch {
+    /// Validate every present field of `settings`
+    pub fn from_partial(p: &PartialMonitorSettings) -> Self {
+        let mut batch = Self::default();
+        let Ok(fields) = serde_json::to_value(p) else {
+            return batch;
+        };
+
+        for def in registry::SETTINGS {
+            let value = &fields[def.field];
+            if value.is_null() {
+                continue;
+            }
+            match def.msigd_value(value) {
+                Ok(value) => batch.entries.push((def.field, def.flag, value)),
+                Err(e) => {
+                    let mut result = FieldResult::failed(def.field, &e);
+                    if let MsigdError::InvalidValue(message) = e {
+                        result.error = Some(message);
+                    }
+                    batch.rejected.push(result);
+                }
+            }
+        }
+
+        batch
+    }
+
+    /// Fields that failed validation
+    pub fn rejected(&self) -> &[FieldResult] {
+        &self.rejected
+    }
+
+    /// (msigd setting, value) pairs that passed validation
+    pub fn args(&self) -> Vec<(String, String)> {
+        self.entries
+            .iter()
+            .map(|(_, setting, value)| (setting.to_string(), value.clone()))
+            .collect()
+    }
+
+    /// Send all valid fields in one call and report the outcome per field
+    ///
+    /// When the error names the setting msigd rejected, only that field
+    /// fails and the others are sent again without it.
+    pub async fn apply(self, backend: &dyn MonitorBackend, monitor_id: &str) -> Vec<FieldResult> {
+        let mut results = self.rejected;
+        let mut pending = self.entries;
+        while !pending.is_empty() {
+            let args: Vec<(String, String)> = pending
+                .iter()
+                .map(|(_, setting, value)| (setting.to_string(), value.clone()))
+                .collect();
+            let Err(error) = backend.set_many(monitor_id, &args).await else {
+                results.extend(pending.iter().map(|(field, _, _)| FieldResult::ok(field)));
+                break;
+            };
+            let rejected = error
+                .setting()
+                .and_then(|flag| pending.iter().position(|(_, setting, _)| *setting == flag));
+            match rejected {
+                Some(i) => {
+                    let (field, _, _) = pending.remove(i);
+                    results.push(FieldResult::failed(field, &error));
+                }
+                None => {
+                    results.extend(
+                        pending
+                            .iter()
+                            .map(|(field, _, _)| FieldResult::failed(field, &error)),
+                    );
+                    break;
+                }
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::verify::VerifiedWrites;
+
+    #[tokio::test]
+    async fn test_apply_sends_one_call() {
+        let backend = MockBackend::default();
+        let partial = PartialMonitorSettings {
+            brightness: Some(30),
+            eye_saver: Some(true),
+            screen_size: Some(ScreenSize::Ratio16x9),
+            refresh_rate_display: Some(true),
+            ..Default::default()
+        };
+
+        let batch = SettingsBatch::from_partial(&partial);
+        assert_eq!(
+            batch.args(),
+            [
+                ("brightness".to_string(), "30".to_string()),
+                ("eye_saver".to_string(), "on".to_string()),
+                ("refresh_display".to_string(), "on".to_string()),
+                ("screen_size".to_string(), "16:9".to_string()),
+            ]
+        );
+
+        let results = batch.apply(&backend, "1").await;
+        assert!(results.iter().all(|r| r.success));
+        let settings = backend.monitor("1").unwrap().settings;
+        assert_eq!(settings.brightness, 30);
+        assert_eq!(settings.screen_size, ScreenSize::Ratio16x9);
+    }
+
+    #[tokio::test]
+    async fn test_apply_reports_invalid_fields() {
+        let backend = MockBackend::default();
+        let partial = PartialMonitorSettings {
+            contrast: Some(150),
+            black_tuner: Some(12),
+            ..Default::default()
+        };
+
+        let results = SettingsBatch::from_partial(&partial)
+            .apply(&backend, "1")
+            .await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].field, "contrast");
+        assert!(!results[0].success);
+        assert_eq!(results[0].error_code.as_deref(), Some("invalid_value"));
+        assert_eq!(results[1].field, "blackTuner");
+        assert!(results[1].success);
+        assert_eq!(backend.writes().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_fails_only_the_named_field() {
+        // The first write is ignored, so read-back flags brightness first
+        let monitor = MockMonitor::new("SN1", "MAG274QRF-QD").ignoring(1);
+        let mock = Arc::new(MockBackend::new(vec![monitor]));
+        let backend = VerifiedWrites::new(mock.clone(), 0).with_settle(Duration::ZERO);
+        let partial = PartialMonitorSettings {
+            brightness: Some(30),
+            eye_saver: Some(true),
+            ..Default::default()
+        };
+
+        let results = SettingsBatch::from_partial(&partial)
+            .apply(&backend, "1")
+            .await;
+
+        assert_eq!(results[0].field, "brightness");
+        assert!(!results[0].success);
+        assert_eq!(results[0].error_code.as_deref(), Some("verify_failed"));
+        assert_eq!(results[1].field, "eyeSaver");
+        assert!(results[1].success);
+        assert!(mock.monitor("1").unwrap().settings.eye_saver);
+
+        // A rejection that names no setting fails the whole call
+        let monitor = MockMonitor::new("SN1", "MAG274QRF-QD").read_only(&["sharpness"]);
+        let backend = MockBackend::new(vec![monitor]);
+        let partial = PartialMonitorSettings {
+            brightness: Some(30),
+            sharpness: Some(3),
+            ..Default::default()
+        };
+        let results = SettingsBatch::from_partial(&partial)
+            .apply(&backend, "1")
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.error_code.as_deref() == Some("command_failed")));
+    }
+}
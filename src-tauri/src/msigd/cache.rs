@@ -0,0 +1,282 @@
+//! Per-monitor cache of `--query` output
+//!
+//! A full query takes noticeable time over HID. Query output is kept for a
+//! TTL and patched after successful writes, so every caller shares one read
+//! of the device. Entries are dropped after failed calls, and all of them
+//! when the monitor list changes since msigd indices may then point at
+//! other monitors.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{MonitorBackend, SharedBackend};
+use crate::msigd::registry::{self, SettingKind};
+
+/// How long query output is served from the cache by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(10);
+
+/// Query output keys the parser reads an RGB color from
+const RGB_KEYS: &[&str] = &[
+    "color_rgb",
+    "color_red",
+    "red",
+    "color_green",
+    "green",
+    "color_blue",
+    "blue",
+];
+
+struct Entry {
+    output: String,
+    fetched_at: Instant,
+}
+
+/// Backend wrapper caching query output per monitor
+///
+/// Wrapped in the [`MonitorQueue`](crate::msigd::queue::MonitorQueue), so
+/// concurrent queries for a monitor wait for the first and then hit the cache.
+pub struct QueryCache {
+    inner: SharedBackend,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+    last_list: Mutex<Option<String>>,
+}
+
+impl QueryCache {
+    pub fn new(inner: SharedBackend, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            last_list: Mutex::new(None),
+        }
+    }
+
+    fn fresh(&self, monitor_id: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(monitor_id)
+            .filter(|entry| entry.fetched_at.elapsed() < self.ttl)
+            .map(|entry| entry.output.clone())
+    }
+
+    fn forget(&self, monitor_id: &str) {
+        self.entries.lock().unwrap().remove(monitor_id);
+    }
+
+    /// Patch cached output with values that were written successfully
+    fn written(&self, monitor_id: &str, settings: &[(String, String)]) {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(monitor_id) else {
+            return;
+        };
+        let patched = settings
+            .iter()
+            .try_fold(entry.output.clone(), |output, (setting, value)| {
+                patch(&output, setting, value)
+            });
+        match patched {
+            Some(output) => entry.output = output,
+            None => {
+                entries.remove(monitor_id);
+            }
+        }
+    }
+
+    /// Patch the entry after a successful write, drop it after a failed one
+    fn record(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+        result: Result<String, MsigdError>,
+    ) -> Result<String, MsigdError> {
+        match &result {
+            Ok(_) => self.written(monitor_id, settings),
+            // The monitor may have applied part of the write
+            Err(_) => self.forget(monitor_id),
+        }
+        result
+    }
+}
+
+/// Query output with `setting` reported as `value`
+///
+/// Returns `None` when the value would not read back as written, so the
+/// caller can drop the entry instead of caching a guess.
+fn patch(output: &str, setting: &str, value: &str) -> Option<String> {
+    let def = registry::by_flag(setting)?;
+    def.parse_value(value)?;
+    let single = [setting];
+    let (replaced, value) = match def.kind {
+        // msigd takes "r,g,b" but reports "r:g:b"
+        SettingKind::Rgb { .. } => (RGB_KEYS, value.replace(',', ":")),
+        _ => (&single[..], value.to_string()),
+    };
+
+    let mut patched: String = output
+        .lines()
+        .filter(|line| {
+            let key = line
+                .split_once(':')
+                .map(|(key, _)| key.trim().to_lowercase().replace(' ', "_"));
+            !key.is_some_and(|key| replaced.contains(&key.as_str()))
+        })
+        .map(|line| format!("{}\n", line))
+        .collect();
+    patched.push_str(&format!("{}: {}\n", setting, value));
+    Some(patched)
+}
+
+#[async_trait]
+impl MonitorBackend for QueryCache {
+    async fn list(&self) -> Result<String, MsigdError> {
+        let output = self.inner.list().await?;
+        let mut last = self.last_list.lock().unwrap();
+        if last.as_deref() != Some(output.as_str()) {
+            self.entries.lock().unwrap().clear();
+            *last = Some(output.clone());
+        }
+        Ok(output)
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        if let Some(output) = self.fresh(monitor_id) {
+            return Ok(output);
+        }
+        match self.inner.query(monitor_id).await {
+            Ok(output) => {
+                let entry = Entry {
+                    output: output.clone(),
+                    fetched_at: Instant::now(),
+                };
+                self.entries
+                    .lock()
+                    .unwrap()
+                    .insert(monitor_id.to_string(), entry);
+                Ok(output)
+            }
+            Err(e) => {
+                self.forget(monitor_id);
+                Err(e)
+            }
+        }
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        let result = self.inner.set(monitor_id, setting, value).await;
+        self.record(monitor_id, &[(setting.to_string(), value.to_string())], result)
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        let result = self.inner.set_many(monitor_id, settings).await;
+        self.record(monitor_id, settings, result)
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.inner.mystic(monitor_id, config).await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        self.forget(monitor_id);
+        self.inner.invalidate(monitor_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::MsigdParser;
+
+    fn cached(ttl: Duration) -> (Arc<MockBackend>, QueryCache) {
+        let mock = Arc::new(MockBackend::new(vec![MockMonitor::new("SN1", "MAG274QRF-QD")]));
+        (mock.clone(), QueryCache::new(mock, ttl))
+    }
+
+    async fn brightness(backend: &dyn MonitorBackend) -> u8 {
+        let output = backend.query("1").await.unwrap();
+        MsigdParser::parse_settings(&output).unwrap().brightness
+    }
+
+    #[tokio::test]
+    async fn test_serves_queries_until_invalidated() {
+        let (mock, cache) = cached(DEFAULT_TTL);
+        let before = brightness(&cache).await;
+
+        // Changed behind the cache's back, like from the monitor's OSD
+        mock.set("1", "brightness", "10").await.unwrap();
+        assert_eq!(brightness(&cache).await, before);
+
+        cache.invalidate("1").await;
+        assert_eq!(brightness(&cache).await, 10);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_queried_again() {
+        let (mock, cache) = cached(Duration::ZERO);
+        brightness(&cache).await;
+
+        mock.set("1", "brightness", "20").await.unwrap();
+        assert_eq!(brightness(&cache).await, 20);
+    }
+
+    #[tokio::test]
+    async fn test_writes_update_cached_output() {
+        let (mock, cache) = cached(DEFAULT_TTL);
+        cache.query("1").await.unwrap();
+
+        cache.set("1", "brightness", "80").await.unwrap();
+        let settings = [
+            ("color_rgb".to_string(), "10,20,30".to_string()),
+            ("eye_saver".to_string(), "on".to_string()),
+        ];
+        cache.set_many("1", &settings).await.unwrap();
+        let writes = mock.writes().len();
+
+        let output = cache.query("1").await.unwrap();
+        assert_eq!(mock.writes().len(), writes);
+        let queried = MsigdParser::parse_query(&output).unwrap();
+        assert_eq!(queried.settings.brightness, 80);
+        let rgb = &queried.settings.color_rgb;
+        assert_eq!((rgb.r, rgb.g, rgb.b), (10, 20, 30));
+        assert!(queried.settings.eye_saver);
+        assert_eq!(MsigdParser::parse_firmware(&output).as_deref(), Some("018"));
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_hotplug_invalidate() {
+        let (mock, cache) = cached(DEFAULT_TTL);
+        cache.list().await.unwrap();
+        cache.query("1").await.unwrap();
+
+        mock.set("1", "brightness", "10").await.unwrap();
+        cache.set("1", "sharpness", "9").await.unwrap_err();
+        assert_eq!(brightness(&cache).await, 10);
+
+        mock.set("1", "brightness", "20").await.unwrap();
+        cache.list().await.unwrap();
+        assert_eq!(brightness(&cache).await, 10);
+        mock.plug(MockMonitor::new("SN2", "MAG321CURV"));
+        cache.list().await.unwrap();
+        assert_eq!(brightness(&cache).await, 20);
+    }
+}
@@ -0,0 +1,55 @@
+//! Per-monitor capability detection
+//!
+//! msigd only reports the settings a model supports, so the fields present in
+//! `--query` output are exactly the controls the monitor has. Nothing is
+//! hidden or added based on the model name.
+
+use serde::{Deserialize, Serialize};
+
+/// Settings a monitor supports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorCapabilities {
+    /// Supported `MonitorSettings` fields, in registry order
+    pub settings: Vec<String>,
+}
+
+impl MonitorCapabilities {
+    /// Derive capabilities from the fields reported by
+    /// `MsigdParser::parse_query`
+    pub fn detect(reported: &[String]) -> Self {
+        Self {
+            settings: reported.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::backend::MonitorBackend;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::MsigdParser;
+
+    #[tokio::test]
+    async fn test_detect_from_query_output() {
+        let monitor = MockMonitor::new("SN1", "MAG321CURV").without(&["kvm", "rgb_led"]);
+        let backend = MockBackend::new(vec![monitor]);
+
+        let queried = MsigdParser::parse_query(&backend.query("1").await.unwrap()).unwrap();
+        let caps = MonitorCapabilities::detect(&queried.reported);
+
+        assert!(caps.settings.contains(&"brightness".to_string()));
+        assert!(caps.settings.contains(&"gameMode".to_string()));
+        assert!(caps.settings.contains(&"proMode".to_string()));
+        assert!(!caps.settings.contains(&"kvm".to_string()));
+        assert!(!caps.settings.contains(&"rgbLed".to_string()));
+    }
+
+    #[test]
+    fn test_detect_keeps_every_reported_setting() {
+        let reported = vec!["gameMode".to_string(), "proMode".to_string(), "rgbLed".to_string()];
+
+        assert_eq!(MonitorCapabilities::detect(&reported).settings, reported);
+    }
+}
@@ -1,18 +1,63 @@
 //! Executor for msigd CLI commands
 
-use std::process::Command;
+use std::io;
+use std::time::Duration;
+
+use tokio::process::Command;
+
 use crate::error::MsigdError;
 
-/// Executor for msigd CLI commands using std::process
-pub struct MsigdExecutor;
+/// Default time allowed for a single msigd invocation
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Executor for msigd CLI commands using tokio::process
+pub struct MsigdExecutor {
+    program: String,
+    timeout: Duration,
+}
+
+impl Default for MsigdExecutor {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMEOUT)
+    }
+}
 
 impl MsigdExecutor {
-    /// Execute msigd with the given arguments
-    pub fn execute(args: &[&str]) -> Result<String, MsigdError> {
-        let output = Command::new("msigd")
+    /// Create an executor that kills msigd after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            program: "msigd".to_string(),
+            timeout,
+        }
+    }
+
+    /// Execute msigd with the given arguments using the configured timeout
+    pub async fn execute(&self, args: &[&str]) -> Result<String, MsigdError> {
+        self.execute_with_timeout(args, self.timeout).await
+    }
+
+    /// Execute msigd with the given arguments, killing it after `timeout`
+    pub async fn execute_with_timeout(
+        &self,
+        args: &[&str],
+        timeout: Duration,
+    ) -> Result<String, MsigdError> {
+        let child = Command::new(&self.program)
             .args(args)
-            .output()
-            .map_err(|e| MsigdError::ExecutionFailed(e.to_string()))?;
+            .kill_on_drop(true)
+            .output();
+
+        // Dropping the output future on timeout kills the child
+        let output = tokio::time::timeout(timeout, child)
+            .await
+            .map_err(|_| MsigdError::Timeout(timeout.as_millis() as u64))?
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => MsigdError::NotInstalled,
+                io::ErrorKind::PermissionDenied => MsigdError::PermissionDenied {
+                    stderr: e.to_string(),
+                },
+                _ => MsigdError::ExecutionFailed(e.to_string()),
+            })?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -25,70 +70,126 @@ impl MsigdExecutor {
             Ok(stdout)
         } else if output.status.success() {
             Ok(stdout)
-        } else if !stderr.is_empty() {
-            Err(MsigdError::CommandFailed(stderr))
         } else {
-            Err(MsigdError::CommandFailed(stdout))
+            let stderr = if stderr.is_empty() { stdout } else { stderr };
+            Err(failure(stderr, output.status.code()))
         }
     }
 
     /// List all connected monitors
-    pub fn list_monitors() -> Result<String, MsigdError> {
-        Self::execute(&["--list"])
+    pub async fn list_monitors(&self) -> Result<String, MsigdError> {
+        self.execute(&["--list"]).await
     }
 
     /// Query settings for a specific monitor
-    pub fn query_monitor(monitor_id: &str) -> Result<String, MsigdError> {
-        Self::execute(&["--monitor", monitor_id, "--query", "--numeric"])
+    pub async fn query_monitor(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.execute(&["--monitor", monitor_id, "--query", "--numeric"])
+            .await
     }
 
-    /// Set a numeric setting (brightness, contrast, sharpness)
-    pub fn set_numeric(
-        monitor_id: &str,
-        setting: &str,
-        value: u8,
-    ) -> Result<String, MsigdError> {
-        let value_str = value.to_string();
-        let flag = format!("--{}", setting);
-        Self::execute(&["--monitor", monitor_id, &flag, &value_str])
-    }
-
-    /// Set an enum setting (response_time, eye_saver, etc.)
-    pub fn set_enum(
+    /// Set a single setting to a value in msigd syntax
+    pub async fn set_enum(
+        &self,
         monitor_id: &str,
         setting: &str,
         value: &str,
     ) -> Result<String, MsigdError> {
         let flag = format!("--{}", setting);
-        Self::execute(&["--monitor", monitor_id, &flag, value])
+        self.execute(&["--monitor", monitor_id, &flag, value]).await
     }
 
-    /// Set color RGB values
-    /// msigd expects comma-separated format: r,g,b (e.g., "50,50,50")
-    pub fn set_color_rgb(
+    /// Set several settings in a single msigd invocation
+    ///
+    /// `settings` holds (setting, value) pairs using msigd flag names.
+    pub async fn set_many(
+        &self,
         monitor_id: &str,
-        r: u8,
-        g: u8,
-        b: u8,
+        settings: &[(String, String)],
     ) -> Result<String, MsigdError> {
-        let value = format!("{},{},{}", r, g, b);
-        Self::execute(&["--monitor", monitor_id, "--color_rgb", &value])
+        let flags: Vec<String> = settings
+            .iter()
+            .map(|(setting, _)| format!("--{}", setting))
+            .collect();
+
+        let mut args = vec!["--monitor", monitor_id];
+        for (flag, (_, value)) in flags.iter().zip(settings) {
+            args.push(flag);
+            args.push(value);
+        }
+        self.execute(&args).await
     }
 
     /// Set Mystic Light LED configuration
-    pub fn set_mystic_light(
+    pub async fn set_mystic_light(
+        &self,
         monitor_id: &str,
         config: &str,
     ) -> Result<String, MsigdError> {
-        Self::execute(&["--monitor", monitor_id, "--mystic", config])
+        self.execute(&["--monitor", monitor_id, "--mystic", config])
+            .await
     }
 
     /// Check if msigd binary exists and is accessible
-    pub fn check_available() -> Result<bool, MsigdError> {
-        match Self::execute(&["--help"]) {
+    pub async fn check_available(&self) -> Result<bool, MsigdError> {
+        match self.execute(&["--help"]).await {
             Ok(_) => Ok(true),
-            Err(MsigdError::CommandFailed(_)) => Ok(true), // --help might exit with non-zero
+            // --help might exit with non-zero
+            Err(MsigdError::CommandFailed { .. } | MsigdError::PermissionDenied { .. }) => Ok(true),
             Err(_) => Ok(false),
         }
     }
 }
+
+/// Classify a failed msigd run from its output
+fn failure(stderr: String, exit_status: Option<i32>) -> MsigdError {
+    let lower = stderr.to_lowercase();
+    // hidapi reports EACCES when the udev rule for /dev/hidraw* is missing
+    if lower.contains("permission denied") || lower.contains("eacces") {
+        MsigdError::PermissionDenied { stderr }
+    } else {
+        MsigdError::CommandFailed {
+            stderr,
+            exit_status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_execute_times_out() {
+        let executor = MsigdExecutor {
+            program: "sleep".to_string(),
+            timeout: Duration::from_millis(50),
+        };
+
+        let result = executor.execute(&["5"]).await;
+        assert!(matches!(result, Err(MsigdError::Timeout(50))));
+    }
+
+    #[tokio::test]
+    async fn test_execute_classifies_failures() {
+        let missing = MsigdExecutor {
+            program: "msigd-gui-no-such-program".to_string(),
+            timeout: DEFAULT_TIMEOUT,
+        };
+        assert!(matches!(missing.execute(&[]).await, Err(MsigdError::NotInstalled)));
+
+        let shell = MsigdExecutor {
+            program: "sh".to_string(),
+            timeout: DEFAULT_TIMEOUT,
+        };
+        let denied = shell
+            .execute(&["-c", "echo 'open /dev/hidraw3: Permission denied' >&2; exit 1"])
+            .await;
+        assert!(matches!(denied, Err(MsigdError::PermissionDenied { .. })));
+
+        let failed = shell.execute(&["-c", "echo 'bad value' >&2; exit 3"]).await;
+        assert!(matches!(
+            failed,
+            Err(MsigdError::CommandFailed { exit_status: Some(3), .. })
+        ));
+    }
+}
@@ -0,0 +1,360 @@
+//! Stable monitor identifiers
+//!
+//! msigd numbers monitors in enumeration order, which changes across reboots
+//! and hotplug. Monitors are identified by serial instead, falling back to
+//! model and device path when the serial is missing or shared, and
+//! [`StableIds`] translates those ids back to the current msigd index.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{self, MonitorBackend, SharedBackend};
+use crate::msigd::parser::MsigdParser;
+use crate::msigd::types::Monitor;
+
+/// Stable id for `monitor` among the currently connected `monitors`
+pub fn stable_id(monitor: &Monitor, monitors: &[Monitor]) -> String {
+    let serial = monitor.serial.trim();
+    let shared = monitors.iter().filter(|m| m.serial.trim() == serial).count() > 1;
+    if !serial.is_empty() && !shared {
+        return serial.to_string();
+    }
+    // Without a usable serial the device path is the best stable hint
+    let location = monitor.device_path.as_deref().unwrap_or(&monitor.index);
+    format!("{}@{}", monitor.model, location)
+}
+
+/// Replace the msigd index in each monitor's `id` with its stable id
+pub fn assign_ids(monitors: &mut [Monitor]) {
+    let ids: Vec<String> = monitors.iter().map(|m| stable_id(m, monitors)).collect();
+    for (monitor, id) in monitors.iter_mut().zip(ids) {
+        monitor.id = id;
+    }
+}
+
+/// Maps monitor ids given by callers to stable ids
+///
+/// For backend wrappers that keep per-monitor state, which must be keyed by
+/// stable id even when a caller passes an msigd index.
+#[derive(Default)]
+pub struct StableIdLookup {
+    /// Stable ids seen in the last monitor list
+    ids: Mutex<HashSet<String>>,
+}
+
+impl StableIdLookup {
+    /// Update the known ids from `--list` output passing through the wrapper
+    pub fn remember(&self, list_output: &str) {
+        if let Ok(mut monitors) = MsigdParser::parse_monitor_list(list_output) {
+            assign_ids(&mut monitors);
+            *self.ids.lock().unwrap() = monitors.into_iter().map(|m| m.id).collect();
+        }
+    }
+
+    /// Stable id for a monitor given by stable id or msigd index
+    pub async fn resolve(&self, backend: &dyn MonitorBackend, monitor_id: &str) -> Option<String> {
+        if self.ids.lock().unwrap().contains(monitor_id) {
+            return Some(monitor_id.to_string());
+        }
+        // Indices shift when monitors are re-enumerated, so use a fresh list
+        let monitors = backend::monitors(backend).await.ok()?;
+        *self.ids.lock().unwrap() = monitors.iter().map(|m| m.id.clone()).collect();
+        monitors
+            .into_iter()
+            .find(|m| m.id == monitor_id || m.index == monitor_id)
+            .map(|m| m.id)
+    }
+}
+
+/// Where a stable id was found in the last monitor list
+#[derive(Debug, Clone, PartialEq)]
+struct Slot {
+    index: String,
+    model: String,
+}
+
+/// Backend wrapper accepting stable ids in place of msigd indices
+///
+/// The id-to-index mapping is refreshed on every `list` and whenever an
+/// unknown id is used. Monitors can also be re-enumerated between lists, and
+/// identical monitors can swap indices without any call failing, so writes
+/// through a stable id always look up the index in a fresh list. A query
+/// that fails because its index is gone or now holds another model refreshes
+/// the mapping and is retried once at the new index. Plain msigd indices are
+/// still accepted and never remapped.
+pub struct StableIds {
+    inner: SharedBackend,
+    slots: Mutex<HashMap<String, Slot>>,
+}
+
+impl StableIds {
+    pub fn new(inner: SharedBackend) -> Self {
+        Self {
+            inner,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn remember(&self, output: &str) -> Result<(), MsigdError> {
+        let mut monitors = MsigdParser::parse_monitor_list(output)?;
+        assign_ids(&mut monitors);
+        *self.slots.lock().unwrap() = monitors
+            .into_iter()
+            .map(|m| {
+                let slot = Slot {
+                    index: m.index,
+                    model: m.model,
+                };
+                (m.id, slot)
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn slot(&self, monitor_id: &str) -> Option<Slot> {
+        self.slots.lock().unwrap().get(monitor_id).cloned()
+    }
+
+    fn lookup(&self, monitor_id: &str) -> Option<String> {
+        if let Some(slot) = self.slot(monitor_id) {
+            return Some(slot.index);
+        }
+        self.slots
+            .lock()
+            .unwrap()
+            .values()
+            .any(|slot| slot.index == monitor_id)
+            .then(|| monitor_id.to_string())
+    }
+
+    /// Current msigd index for a stable id or index
+    async fn resolve(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        if let Some(index) = self.lookup(monitor_id) {
+            return Ok(index);
+        }
+        // The monitors may have been re-enumerated since the last list
+        self.list().await?;
+        self.lookup(monitor_id)
+            .ok_or_else(|| MsigdError::MonitorNotFound(monitor_id.to_string()))
+    }
+
+    /// Run the write `op` with the index `monitor_id` has right now
+    async fn with_fresh_index<T, F, Fut>(&self, monitor_id: &str, op: F) -> Result<T, MsigdError>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<T, MsigdError>>,
+    {
+        if self.slot(monitor_id).is_none() {
+            return op(self.resolve(monitor_id).await?).await;
+        }
+        self.list().await?;
+        match self.slot(monitor_id) {
+            Some(slot) => op(slot.index).await,
+            None => Err(MsigdError::MonitorNotFound(monitor_id.to_string())),
+        }
+    }
+
+    /// Run `op` with the current index for `monitor_id`
+    ///
+    /// A stable id whose call fails with its index gone, or rejected because
+    /// another model now sits at that index, is re-listed and retried once.
+    async fn with_index<T, F, Fut>(&self, monitor_id: &str, op: F) -> Result<T, MsigdError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, MsigdError>>,
+    {
+        let index = self.resolve(monitor_id).await?;
+        let result = op(index.clone()).await;
+        let (Err(error), Some(before)) = (&result, self.slot(monitor_id)) else {
+            return result;
+        };
+        if !matches!(
+            error.root(),
+            MsigdError::MonitorNotFound(_) | MsigdError::CommandFailed { .. }
+        ) {
+            return result;
+        }
+        if self.list().await.is_err() {
+            return result;
+        }
+
+        let now_at_index = self
+            .slots
+            .lock()
+            .unwrap()
+            .values()
+            .find(|slot| slot.index == index)
+            .cloned();
+        let model_mismatch = now_at_index.is_some_and(|slot| slot.model != before.model);
+        match self.slot(monitor_id) {
+            Some(slot) if slot.index != index || model_mismatch => op(slot.index).await,
+            Some(_) => result,
+            None => Err(MsigdError::MonitorNotFound(monitor_id.to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for StableIds {
+    async fn list(&self) -> Result<String, MsigdError> {
+        let output = self.inner.list().await?;
+        self.remember(&output)?;
+        Ok(output)
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.with_index(monitor_id, |index| async move {
+            self.inner.query(&index).await
+        })
+        .await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        self.with_fresh_index(monitor_id, |index| async move {
+            self.inner.set(&index, setting, value).await
+        })
+        .await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        self.with_fresh_index(monitor_id, |index| async move {
+            self.inner.set_many(&index, settings).await
+        })
+        .await
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.with_fresh_index(monitor_id, |index| async move {
+            self.inner.mystic(&index, config).await
+        })
+        .await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        if let Ok(index) = self.resolve(monitor_id).await {
+            self.inner.invalidate(&index).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::msigd::backend;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+
+    #[tokio::test]
+    async fn test_assign_ids_falls_back_for_shared_serials() {
+        let backend = MockBackend::new(vec![
+            MockMonitor::new("SN1", "MSI Gaming Controller"),
+            MockMonitor::new("SN1", "MSI Gaming Controller"),
+            MockMonitor::new("SN2", "MAG274QRF-QD"),
+            MockMonitor::new(" ", "MAG321CURV"),
+        ]);
+
+        let ids: Vec<String> = backend::monitors(&backend)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "MSI Gaming Controller@/dev/hidraw0",
+                "MSI Gaming Controller@/dev/hidraw1",
+                "SN2",
+                "MAG321CURV@/dev/hidraw3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stable_ids_follow_reenumeration() {
+        let mock = Arc::new(MockBackend::new(vec![
+            MockMonitor::new("SN1", "MAG274QRF-QD"),
+            MockMonitor::new("SN2", "MAG321CURV"),
+        ]));
+        let ids = StableIds::new(mock.clone());
+
+        ids.set("SN2", "brightness", "80").await.unwrap();
+        mock.unplug("1");
+        ids.list().await.unwrap();
+        ids.set("SN2", "brightness", "60").await.unwrap();
+        // Plain indices keep working
+        ids.set("1", "contrast", "40").await.unwrap();
+
+        let writes: Vec<String> = mock.writes().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(writes, ["2", "1", "1"]);
+        assert_eq!(mock.monitor("1").unwrap().settings.brightness, 60);
+        assert!(matches!(
+            ids.query("SN1").await,
+            Err(MsigdError::MonitorNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stale_index_is_refreshed_and_retried() {
+        let mock = Arc::new(MockBackend::new(vec![
+            MockMonitor::new("SN1", "MAG274QRF-QD"),
+            MockMonitor::new("SN2", "MAG321CURV"),
+        ]));
+        let ids = StableIds::new(mock.clone());
+        ids.list().await.unwrap();
+
+        mock.set("2", "brightness", "60").await.unwrap();
+
+        // Re-enumerated without a list in between: index 2 no longer exists
+        mock.unplug("1");
+        let output = ids.query("SN2").await.unwrap();
+        assert_eq!(MsigdParser::parse_settings(&output).unwrap().brightness, 60);
+
+        // Plain indices are not remapped
+        assert!(matches!(
+            ids.query("2").await,
+            Err(MsigdError::MonitorNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_writes_follow_swapped_identical_monitors() {
+        let mock = Arc::new(MockBackend::new(vec![
+            MockMonitor::new("SN1", "MAG274QRF-QD"),
+            MockMonitor::new("SN2", "MAG274QRF-QD"),
+        ]));
+        let ids = StableIds::new(mock.clone());
+        ids.list().await.unwrap();
+
+        // Replugged: SN2 is now index 1 and SN1 index 2, same model at both
+        mock.unplug("1");
+        mock.plug(MockMonitor::new("SN1", "MAG274QRF-QD"));
+        ids.set("SN1", "brightness", "10").await.unwrap();
+        ids.set_many("SN2", &[("contrast".to_string(), "20".to_string())])
+            .await
+            .unwrap();
+
+        let writes: Vec<String> = mock.writes().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(writes, ["2", "1"]);
+        assert_eq!(mock.monitor("2").unwrap().settings.brightness, 10);
+        assert_eq!(mock.monitor("1").unwrap().settings.contrast, 20);
+    }
+}
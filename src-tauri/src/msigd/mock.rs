@@ -0,0 +1,349 @@
+//! In-process mock backend simulating MSI monitors
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::parser::MsigdParser;
+use crate::msigd::registry::{self, SettingKind};
+use crate::msigd::types::MonitorSettings;
+
+/// A simulated monitor held by the mock backend
+#[derive(Debug, Clone)]
+pub struct MockMonitor {
+    pub serial: String,
+    pub model: String,
+    pub firmware: String,
+    pub settings: MonitorSettings,
+    pub mystic: Option<String>,
+    /// msigd flags left out of query output, as for models lacking them
+    pub unsupported: Vec<String>,
+    /// msigd flags the monitor refuses to write
+    pub read_only: Vec<String>,
+    /// Number of upcoming writes the monitor accepts but silently ignores
+    pub ignored_writes: usize,
+}
+
+impl MockMonitor {
+    pub fn new(serial: &str, model: &str) -> Self {
+        Self {
+            serial: serial.to_string(),
+            model: model.to_string(),
+            firmware: "018".to_string(),
+            settings: MonitorSettings::default(),
+            mystic: None,
+            unsupported: Vec::new(),
+            read_only: Vec::new(),
+            ignored_writes: 0,
+        }
+    }
+
+    /// Simulate a model that does not report the given msigd flags
+    #[cfg(test)]
+    pub fn without(mut self, flags: &[&str]) -> Self {
+        self.unsupported = flags.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    /// Simulate a model that rejects writes to the given msigd flags
+    #[cfg(test)]
+    pub fn read_only(mut self, flags: &[&str]) -> Self {
+        self.read_only = flags.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    /// Simulate a monitor that ignores the next `count` writes
+    #[cfg(test)]
+    pub fn ignoring(mut self, count: usize) -> Self {
+        self.ignored_writes = count;
+        self
+    }
+
+    fn writable(&self, flag: &str) -> bool {
+        !self.read_only.iter().any(|f| f == flag)
+    }
+
+    /// Whether the next write should be dropped, consuming one ignored write
+    fn ignore_write(&mut self) -> bool {
+        let ignore = self.ignored_writes > 0;
+        self.ignored_writes = self.ignored_writes.saturating_sub(1);
+        ignore
+    }
+}
+
+/// Backend that keeps monitor state in memory instead of talking to hardware
+///
+/// Monitor ids are the 1-based position in the list, as with msigd.
+pub struct MockBackend {
+    monitors: Mutex<Vec<MockMonitor>>,
+    latency: Duration,
+    writes: Mutex<Vec<(String, String, String)>>,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new(vec![MockMonitor::new("SN123456789", "MAG274QRF-QD")])
+    }
+}
+
+impl MockBackend {
+    pub fn new(monitors: Vec<MockMonitor>) -> Self {
+        Self {
+            monitors: Mutex::new(monitors),
+            latency: Duration::ZERO,
+            writes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Simulate slow HID transfers by delaying every call
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Every write received so far as (monitor_id, setting, value)
+    #[cfg(test)]
+    pub fn writes(&self) -> Vec<(String, String, String)> {
+        self.writes.lock().unwrap().clone()
+    }
+
+    /// Snapshot of a simulated monitor's current state
+    #[cfg(test)]
+    pub fn monitor(&self, monitor_id: &str) -> Option<MockMonitor> {
+        let monitors = self.monitors.lock().unwrap();
+        Self::index(&monitors, monitor_id).map(|i| monitors[i].clone())
+    }
+
+    /// Connect another simulated monitor at the end of the list
+    #[cfg(test)]
+    pub fn plug(&self, monitor: MockMonitor) {
+        self.monitors.lock().unwrap().push(monitor);
+    }
+
+    /// Disconnect a simulated monitor, renumbering the ones after it
+    #[cfg(test)]
+    pub fn unplug(&self, monitor_id: &str) {
+        let mut monitors = self.monitors.lock().unwrap();
+        if let Some(index) = Self::index(&monitors, monitor_id) {
+            monitors.remove(index);
+        }
+    }
+
+    fn index(monitors: &[MockMonitor], monitor_id: &str) -> Option<usize> {
+        let index: usize = monitor_id.parse().ok()?;
+        (1..=monitors.len()).contains(&index).then(|| index - 1)
+    }
+
+    async fn with_monitor<T>(
+        &self,
+        monitor_id: &str,
+        f: impl FnOnce(&mut MockMonitor) -> Result<T, MsigdError>,
+    ) -> Result<T, MsigdError> {
+        tokio::time::sleep(self.latency).await;
+        let mut monitors = self.monitors.lock().unwrap();
+        let index = Self::index(&monitors, monitor_id)
+            .ok_or_else(|| MsigdError::MonitorNotFound(monitor_id.to_string()))?;
+        f(&mut monitors[index])
+    }
+
+    /// Apply a single msigd setting by round-tripping through the parser
+    ///
+    /// Returns `None` when the parser does not accept the value.
+    fn apply(settings: &MonitorSettings, setting: &str, value: &str) -> Option<MonitorSettings> {
+        // msigd takes "r,g,b" but reports "r:g:b"
+        let value = if setting == "color_rgb" {
+            value.replace(',', ":")
+        } else {
+            value.to_string()
+        };
+        let line = format!("{}: {}", setting, value);
+
+        let mut output = render_settings(settings);
+        output.push_str(&line);
+        let updated = MsigdParser::parse_settings(&output).ok()?;
+
+        render_settings(&updated)
+            .lines()
+            .any(|l| l == line)
+            .then_some(updated)
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for MockBackend {
+    async fn list(&self) -> Result<String, MsigdError> {
+        let monitors = self.monitors.lock().unwrap();
+        Ok(monitors
+            .iter()
+            .enumerate()
+            .map(|(i, m)| format!("{},{},MS,{},/dev/hidraw{}\n", i + 1, m.serial, m.model, i))
+            .collect())
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.with_monitor(monitor_id, |m| {
+            Ok(render_settings(&m.settings)
+                .lines()
+                .filter(|line| {
+                    let flag = line.split(':').next().unwrap_or_default();
+                    !m.unsupported.iter().any(|u| u == flag)
+                })
+                .map(|line| format!("{}\n", line))
+                .chain(std::iter::once(format!("firmware: {}\n", m.firmware)))
+                .collect())
+        })
+        .await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        self.writes.lock().unwrap().push((
+            monitor_id.to_string(),
+            setting.to_string(),
+            value.to_string(),
+        ));
+        self.with_monitor(monitor_id, |m| {
+            let updated = Self::apply(&m.settings, setting, value)
+                .filter(|_| m.writable(setting))
+                .ok_or_else(|| rejected(setting, value))?;
+            if !m.ignore_write() {
+                m.settings = updated;
+            }
+            Ok(String::new())
+        })
+        .await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        self.writes.lock().unwrap().extend(
+            settings
+                .iter()
+                .map(|(setting, value)| (monitor_id.to_string(), setting.clone(), value.clone())),
+        );
+        // Like msigd, reject the whole call if any value is invalid
+        self.with_monitor(monitor_id, |m| {
+            let mut updated = m.settings.clone();
+            for (setting, value) in settings {
+                updated = Self::apply(&updated, setting, value)
+                    .filter(|_| m.writable(setting))
+                    .ok_or_else(|| rejected(setting, value))?;
+            }
+            if !m.ignore_write() {
+                m.settings = updated;
+            }
+            Ok(String::new())
+        })
+        .await
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.with_monitor(monitor_id, |m| {
+            m.mystic = Some(config.to_string());
+            Ok(String::new())
+        })
+        .await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        Ok(true)
+    }
+}
+
+/// Error msigd reports for a value it does not accept
+fn rejected(setting: &str, value: &str) -> MsigdError {
+    MsigdError::CommandFailed {
+        stderr: format!("Invalid value for {}: {}", setting, value),
+        exit_status: Some(1),
+    }
+}
+
+/// Render settings in msigd `--query` format
+fn render_settings(s: &MonitorSettings) -> String {
+    let Ok(fields) = serde_json::to_value(s) else {
+        return String::new();
+    };
+    registry::SETTINGS
+        .iter()
+        .filter_map(|def| {
+            let value = def.msigd_value(&fields[def.field]).ok()?;
+            // msigd takes "r,g,b" but reports "r:g:b"
+            let value = match def.kind {
+                SettingKind::Rgb { .. } => value.replace(',', ":"),
+                _ => value,
+            };
+            Some(format!("{}: {}\n", def.flag, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::types::*;
+
+    #[tokio::test]
+    async fn test_list_tells_identical_monitors_apart() {
+        let mut second = MockMonitor::new("SN1", "MSI Gaming Controller");
+        second.firmware = "019".to_string();
+        let backend = MockBackend::new(vec![
+            MockMonitor::new("SN1", "MSI Gaming Controller"),
+            second,
+        ]);
+
+        let monitors = crate::msigd::backend::list_monitors(&backend).await.unwrap();
+        assert_eq!(monitors[0].identity, "SN1@/dev/hidraw0");
+        assert_eq!(monitors[1].identity, "SN1@/dev/hidraw1");
+        assert_eq!(monitors[0].firmware, "018");
+        assert_eq!(monitors[1].firmware, "019");
+    }
+
+    #[tokio::test]
+    async fn test_query_round_trips_through_parser() {
+        let backend = MockBackend::default();
+        let output = backend.query("1").await.unwrap();
+        let settings = MsigdParser::parse_settings(&output).unwrap();
+
+        let defaults = MonitorSettings::default();
+        assert_eq!(settings.brightness, defaults.brightness);
+        assert_eq!(settings.screen_size, defaults.screen_size);
+        assert_eq!(settings.sound_enable, defaults.sound_enable);
+    }
+
+    #[tokio::test]
+    async fn test_set_updates_settings() {
+        let backend = MockBackend::default();
+        backend.set("1", "brightness", "80").await.unwrap();
+        backend.set("1", "screen_size", "21:9").await.unwrap();
+        backend.set("1", "refresh_display", "on").await.unwrap();
+        backend.set("1", "color_rgb", "10,20,30").await.unwrap();
+
+        let settings = backend.monitor("1").unwrap().settings;
+        assert_eq!(settings.brightness, 80);
+        assert_eq!(settings.screen_size, ScreenSize::Ratio21x9);
+        assert!(settings.refresh_rate_display);
+        let rgb = settings.color_rgb;
+        assert_eq!((rgb.r, rgb.g, rgb.b), (10, 20, 30));
+    }
+
+    #[tokio::test]
+    async fn test_set_rejects_invalid_values() {
+        let backend = MockBackend::default();
+        assert!(backend.set("1", "game_mode", "turbo").await.is_err());
+        assert!(backend.set("1", "no_such_setting", "on").await.is_err());
+        assert!(matches!(
+            backend.set("2", "brightness", "10").await,
+            Err(MsigdError::MonitorNotFound(_))
+        ));
+    }
+}
@@ -1,9 +1,20 @@
 //! msigd CLI wrapper module
 
+pub mod backend;
+pub mod batch;
+pub mod cache;
+pub mod capabilities;
 pub mod executor;
+pub mod identity;
+pub mod mock;
+pub mod mystic;
 pub mod parser;
+pub mod queue;
+pub mod registry;
 pub mod types;
+pub mod verify;
 
-pub use executor::MsigdExecutor;
+pub use backend::SharedBackend;
+pub use mystic::MysticLightConfig;
 pub use parser::MsigdParser;
 pub use types::*;
@@ -0,0 +1,388 @@
+//! Mystic Light LED configuration
+//!
+//! msigd takes `--mystic <ledgroup>:<mode>:<colors>`, where `ledgroup` is 0 or
+//! 1 and `colors` is a comma-separated list of `0xRRGGBB` values, e.g.
+//! `0:breathing:0xFF0000,0x0000FF`. Modes without colors still take a
+//! placeholder color: `0:rainbow:0x000000`. This is the format the LED tab
+//! has always sent to msigd.
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::MsigdError;
+
+/// LED groups msigd accepts
+const LED_GROUPS: &[&str] = &["0", "1"];
+
+/// Color sent for modes that take none
+const PLACEHOLDER_COLOR: &str = "0x000000";
+
+/// Mystic Light config for one LED group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MysticLightConfig {
+    /// LED group, "0" or "1"
+    pub led_group: String,
+    pub mode: MysticLightMode,
+    /// Colors as "#rrggbb", "0xrrggbb" or "rrggbb"
+    pub colors: Vec<String>,
+}
+
+/// Mystic Light config remembered from the last successful write
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastKnownMysticLight {
+    pub config: MysticLightConfig,
+    /// Seconds since the Unix epoch
+    pub applied_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MysticLightMode {
+    Off,
+    Static,
+    Breathing,
+    Blinking,
+    Flashing,
+    DoubleFlashing,
+    Blinds,
+    Lightning,
+    Meteor,
+    Rainbow,
+    Random,
+    RainbowGradient,
+}
+
+impl MysticLightMode {
+    const ALL: [Self; 12] = [
+        Self::Off,
+        Self::Static,
+        Self::Breathing,
+        Self::Blinking,
+        Self::Flashing,
+        Self::DoubleFlashing,
+        Self::Blinds,
+        Self::Lightning,
+        Self::Meteor,
+        Self::Rainbow,
+        Self::Random,
+        Self::RainbowGradient,
+    ];
+
+    /// Name used in msigd's mystic syntax
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Static => "static",
+            Self::Breathing => "breathing",
+            Self::Blinking => "blinking",
+            Self::Flashing => "flashing",
+            Self::DoubleFlashing => "double_flashing",
+            Self::Blinds => "blinds",
+            Self::Lightning => "lightning",
+            Self::Meteor => "meteor",
+            Self::Rainbow => "rainbow",
+            Self::Random => "random",
+            Self::RainbowGradient => "rainbow_gradient",
+        }
+    }
+
+    /// Number of colors the mode takes
+    ///
+    /// Matches what the LED tab always sent: only breathing and blinking
+    /// take a second color.
+    pub fn color_count(&self) -> RangeInclusive<usize> {
+        match self {
+            Self::Off | Self::Rainbow | Self::Random => 0..=0,
+            // Alternates between the first and optional second color
+            Self::Breathing | Self::Blinking => 1..=2,
+            Self::Static
+            | Self::Flashing
+            | Self::DoubleFlashing
+            | Self::Blinds
+            | Self::Lightning
+            | Self::Meteor
+            | Self::RainbowGradient => 1..=1,
+        }
+    }
+}
+
+/// Color counts of a mode, as sent to the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MysticLightModeInfo {
+    pub mode: MysticLightMode,
+    pub min_colors: usize,
+    pub max_colors: usize,
+}
+
+/// Describe every mode so the LED tab knows how many colors to send
+pub fn modes() -> Vec<MysticLightModeInfo> {
+    MysticLightMode::ALL
+        .iter()
+        .map(|&mode| {
+            let range = mode.color_count();
+            MysticLightModeInfo {
+                mode,
+                min_colors: *range.start(),
+                max_colors: *range.end(),
+            }
+        })
+        .collect()
+}
+
+impl MysticLightConfig {
+    /// Check the LED group, color count and colors without formatting
+    pub fn validate(&self) -> Result<(), MsigdError> {
+        self.to_msigd().map(|_| ())
+    }
+
+    /// Validate and format the config for `--mystic`
+    pub fn to_msigd(&self) -> Result<String, MsigdError> {
+        let group = self.led_group.trim();
+        if !LED_GROUPS.contains(&group) {
+            return Err(MsigdError::InvalidValue(format!(
+                "LED group must be {}",
+                LED_GROUPS.join(" or ")
+            )));
+        }
+
+        let range = self.mode.color_count();
+        if !range.contains(&self.colors.len()) {
+            let expected = if range.start() == range.end() {
+                range.start().to_string()
+            } else {
+                format!("{}-{}", range.start(), range.end())
+            };
+            return Err(MsigdError::InvalidValue(format!(
+                "Mystic Light mode {} takes {} colors, got {}",
+                self.mode.name(),
+                expected,
+                self.colors.len()
+            )));
+        }
+
+        let colors = self
+            .colors
+            .iter()
+            .map(|c| normalize_color(c).map(|hex| format!("0x{}", hex.to_uppercase())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let colors = if colors.is_empty() {
+            PLACEHOLDER_COLOR.to_string()
+        } else {
+            colors.join(",")
+        };
+        Ok(format!("{}:{}:{}", group, self.mode.name(), colors))
+    }
+
+    /// Parse a config in the `--mystic` syntax produced by [`Self::to_msigd`]
+    pub fn from_msigd(config: &str) -> Result<Self, MsigdError> {
+        let invalid =
+            || MsigdError::InvalidValue(format!("Invalid Mystic Light config {:?}", config));
+        let mut parts = config.trim().splitn(3, ':');
+        let (Some(group), Some(mode), Some(colors)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let mode = MysticLightMode::ALL
+            .into_iter()
+            .find(|m| m.name() == mode)
+            .ok_or_else(invalid)?;
+        let colors = if *mode.color_count().end() == 0 {
+            Vec::new()
+        } else {
+            colors.split(',').map(str::to_string).collect()
+        };
+
+        let parsed = Self {
+            led_group: group.to_string(),
+            mode,
+            colors,
+        }
+        .normalized();
+        parsed.validate()?;
+        Ok(parsed)
+    }
+
+    /// Copy with colors rewritten as "#rrggbb", leaving invalid ones as is
+    pub fn normalized(&self) -> Self {
+        Self {
+            led_group: self.led_group.trim().to_string(),
+            mode: self.mode,
+            colors: self
+                .colors
+                .iter()
+                .map(|c| normalize_color(c).map_or_else(|_| c.clone(), |hex| format!("#{}", hex)))
+                .collect(),
+        }
+    }
+}
+
+/// Lowercase `rrggbb` from "#rrggbb", "0xrrggbb" or "rrggbb"
+fn normalize_color(color: &str) -> Result<String, MsigdError> {
+    let trimmed = color.trim();
+    let hex = trimmed
+        .strip_prefix('#')
+        .or_else(|| trimmed.strip_prefix("0x"))
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(hex.to_lowercase())
+    } else {
+        Err(MsigdError::InvalidValue(format!(
+            "Invalid color {:?}, expected #rrggbb",
+            color
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MysticLightMode::*;
+
+    fn config(mode: MysticLightMode, colors: &[&str]) -> Result<String, MsigdError> {
+        group_config("0", mode, colors)
+    }
+
+    fn group_config(
+        group: &str,
+        mode: MysticLightMode,
+        colors: &[&str],
+    ) -> Result<String, MsigdError> {
+        MysticLightConfig {
+            led_group: group.to_string(),
+            mode,
+            colors: colors.iter().map(|c| c.to_string()).collect(),
+        }
+        .to_msigd()
+    }
+
+    /// Strings the LED tab's `buildMysticConfig` sent to msigd before the
+    /// config was built in Rust
+    #[test]
+    fn test_matches_previous_frontend_output() {
+        let cases = [
+            (Off, vec![], "0:off:0x000000"),
+            (Rainbow, vec![], "0:rainbow:0x000000"),
+            (Random, vec![], "0:random:0x000000"),
+            (Static, vec!["#ff0000"], "0:static:0xFF0000"),
+            (Flashing, vec!["#00ff00"], "0:flashing:0x00FF00"),
+            (Blinds, vec!["#123456"], "0:blinds:0x123456"),
+            (Meteor, vec!["#abcdef"], "0:meteor:0xABCDEF"),
+            (
+                Breathing,
+                vec!["#ff0000", "#0000ff"],
+                "0:breathing:0xFF0000,0x0000FF",
+            ),
+            (
+                Blinking,
+                vec!["#00ff00", "#ffffff"],
+                "0:blinking:0x00FF00,0xFFFFFF",
+            ),
+        ];
+
+        for (mode, colors, expected) in cases {
+            assert_eq!(config(mode, &colors).unwrap(), expected, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_every_mode_serializes() {
+        let cases = [
+            (Breathing, vec!["#ff0000"], "0:breathing:0xFF0000"),
+            (
+                Blinking,
+                vec!["0x00FF00", "ffffff"],
+                "0:blinking:0x00FF00,0xFFFFFF",
+            ),
+            (DoubleFlashing, vec!["#ff0000"], "0:double_flashing:0xFF0000"),
+            (Lightning, vec!["#ffffff"], "0:lightning:0xFFFFFF"),
+            (RainbowGradient, vec!["#00ff00"], "0:rainbow_gradient:0x00FF00"),
+        ];
+
+        for (mode, colors, expected) in cases {
+            assert_eq!(config(mode, &colors).unwrap(), expected, "{:?}", mode);
+        }
+        for mode in MysticLightMode::ALL {
+            let colors = vec!["#ff0000"; *mode.color_count().start()];
+            assert!(config(mode, &colors).is_ok(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_configs() {
+        assert!(config(Static, &[]).is_err());
+        assert!(config(Rainbow, &["#ff0000"]).is_err());
+        assert!(config(Flashing, &["#ff0000", "#0000ff"]).is_err());
+        assert!(config(DoubleFlashing, &["#ff0000", "#0000ff"]).is_err());
+        assert!(config(RainbowGradient, &["#ff0000", "#00ff00"]).is_err());
+        assert!(config(Static, &["red"]).is_err());
+        assert!(config(Static, &["#ff00"]).is_err());
+
+        assert_eq!(
+            group_config("1", Static, &["#ff0000"]).unwrap(),
+            "1:static:0xFF0000"
+        );
+        assert_eq!(
+            group_config("2", Off, &[]).unwrap_err().to_string(),
+            "Invalid value: LED group must be 0 or 1"
+        );
+        assert!(group_config("all", Off, &[]).is_err());
+    }
+
+    #[test]
+    fn test_parses_msigd_syntax() {
+        let parsed = MysticLightConfig::from_msigd("1:breathing:0xFF0000,0x0000FF").unwrap();
+        assert_eq!(parsed.led_group, "1");
+        assert_eq!(parsed.mode, Breathing);
+        assert_eq!(parsed.colors, ["#ff0000", "#0000ff"]);
+
+        let rainbow = MysticLightConfig::from_msigd("0:rainbow:0x000000").unwrap();
+        assert!(rainbow.colors.is_empty());
+        assert_eq!(rainbow.to_msigd().unwrap(), "0:rainbow:0x000000");
+
+        assert!(MysticLightConfig::from_msigd("0;static;ff0000").is_err());
+        assert!(MysticLightConfig::from_msigd("0:sparkle:0xFF0000").is_err());
+        assert!(MysticLightConfig::from_msigd("0:static:0xFF0000,0x00FF00").is_err());
+    }
+
+    #[test]
+    fn test_deserializes_frontend_config() {
+        let config: MysticLightConfig = serde_json::from_value(serde_json::json!({
+            "ledGroup": "1",
+            "mode": "breathing",
+            "colors": ["#ff0000", "#0000ff"],
+        }))
+        .unwrap();
+
+        assert_eq!(config.mode, Breathing);
+        assert_eq!(config.to_msigd().unwrap(), "1:breathing:0xFF0000,0x0000FF");
+    }
+
+    #[test]
+    fn test_modes_describe_color_counts() {
+        let modes = modes();
+        assert_eq!(modes.len(), MysticLightMode::ALL.len());
+        assert_eq!(
+            serde_json::to_value(modes[2]).unwrap(),
+            serde_json::json!({ "mode": "breathing", "minColors": 1, "maxColors": 2 })
+        );
+        let two_color: Vec<_> = modes.iter().filter(|m| m.max_colors > 1).map(|m| m.mode).collect();
+        assert_eq!(two_color, [Breathing, Blinking]);
+    }
+
+    #[test]
+    fn test_normalized_colors() {
+        let config = MysticLightConfig {
+            led_group: " 1 ".to_string(),
+            mode: Breathing,
+            colors: vec!["0xFF0000".to_string(), "00ff00".to_string()],
+        };
+
+        assert_eq!(config.normalized().led_group, "1");
+        assert_eq!(config.normalized().colors, ["#ff0000", "#00ff00"]);
+    }
+}
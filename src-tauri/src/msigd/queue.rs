@@ -0,0 +1,188 @@
+//! Per-monitor command serialization and write coalescing
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{MonitorBackend, SharedBackend};
+
+/// Pseudo setting name used to coalesce Mystic Light writes
+const MYSTIC_KEY: &str = "mystic";
+
+/// Latest queued write per setting
+#[derive(Default)]
+struct PendingWrites {
+    next_ticket: u64,
+    latest: HashMap<String, u64>,
+}
+
+/// Serialized access to a single monitor
+#[derive(Default)]
+struct MonitorSlot {
+    /// Held while talking to the device; tokio's mutex is FIFO so calls
+    /// reach the monitor in the order they were issued
+    device: tokio::sync::Mutex<()>,
+    pending: Mutex<PendingWrites>,
+}
+
+impl MonitorSlot {
+    fn enqueue(&self, setting: &str) -> u64 {
+        let mut pending = self.pending.lock().unwrap();
+        pending.next_ticket += 1;
+        let ticket = pending.next_ticket;
+        pending.latest.insert(setting.to_string(), ticket);
+        ticket
+    }
+
+    /// Take the pending entry if `ticket` is still the latest for `setting`
+    fn take_if_latest(&self, setting: &str, ticket: u64) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.latest.get(setting) == Some(&ticket) {
+            pending.latest.remove(setting);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Backend wrapper that serializes HID access per monitor
+///
+/// Writes to the same setting that are still waiting for the device are
+/// coalesced: only the most recent value is sent and superseded callers
+/// return immediately with empty output.
+pub struct MonitorQueue {
+    inner: SharedBackend,
+    slots: Mutex<HashMap<String, Arc<MonitorSlot>>>,
+}
+
+impl MonitorQueue {
+    pub fn new(inner: SharedBackend) -> Self {
+        Self {
+            inner,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn slot(&self, monitor_id: &str) -> Arc<MonitorSlot> {
+        self.slots
+            .lock()
+            .unwrap()
+            .entry(monitor_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Run a write for `setting`, skipping it if a newer one was queued
+    async fn write<F>(&self, monitor_id: &str, setting: &str, f: F) -> Result<String, MsigdError>
+    where
+        F: Future<Output = Result<String, MsigdError>>,
+    {
+        let slot = self.slot(monitor_id);
+        let ticket = slot.enqueue(setting);
+        let _device = slot.device.lock().await;
+
+        if !slot.take_if_latest(setting, ticket) {
+            return Ok(String::new());
+        }
+        f.await
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for MonitorQueue {
+    async fn list(&self) -> Result<String, MsigdError> {
+        self.inner.list().await
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        let slot = self.slot(monitor_id);
+        let _device = slot.device.lock().await;
+        self.inner.query(monitor_id).await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        self.write(monitor_id, setting, self.inner.set(monitor_id, setting, value))
+            .await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        let slot = self.slot(monitor_id);
+        let _device = slot.device.lock().await;
+        self.inner.set_many(monitor_id, settings).await
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.write(monitor_id, MYSTIC_KEY, self.inner.mystic(monitor_id, config))
+            .await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        self.inner.invalidate(monitor_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::msigd::mock::MockBackend;
+
+    #[tokio::test]
+    async fn test_coalesces_pending_writes() {
+        let mock = Arc::new(MockBackend::default().with_latency(Duration::from_millis(20)));
+        let queue = Arc::new(MonitorQueue::new(mock.clone()));
+
+        let handles: Vec<_> = ["10", "20", "30", "40", "50"]
+            .into_iter()
+            .map(|value| {
+                let queue = queue.clone();
+                tokio::spawn(async move { queue.set("1", "brightness", value).await })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        // First write was already in flight, the rest collapse into the last value
+        let writes: Vec<_> = mock.writes().into_iter().map(|w| w.2).collect();
+        assert_eq!(writes, ["10", "50"]);
+        assert_eq!(mock.monitor("1").unwrap().settings.brightness, 50);
+    }
+
+    #[tokio::test]
+    async fn test_different_settings_are_not_coalesced() {
+        let mock = Arc::new(MockBackend::default().with_latency(Duration::from_millis(20)));
+        let queue = Arc::new(MonitorQueue::new(mock.clone()));
+
+        let brightness = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.set("1", "brightness", "70").await })
+        };
+        let contrast = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.set("1", "contrast", "30").await })
+        };
+        brightness.await.unwrap().unwrap();
+        contrast.await.unwrap().unwrap();
+
+        assert_eq!(mock.writes().len(), 2);
+    }
+}
@@ -0,0 +1,349 @@
+//! Declarative registry of every monitor setting
+//!
+//! Each entry describes how a `MonitorSettings` field maps to msigd: its flag
+//! name, value kind, numeric range or allowed values. Choice values are listed
+//! in msigd's numeric code order, so the index of a value is its code in
+//! `--query --numeric` output. Validation, parsing and serialization are all
+//! driven from this table, and the frontend receives it via
+//! `get_setting_schema`.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::error::MsigdError;
+
+/// Value kind of a setting
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SettingKind {
+    /// Integer in `min..=max`
+    Numeric { min: u8, max: u8 },
+    /// "on" or "off"
+    Toggle,
+    /// One of `values`; the position of a value is its numeric code
+    Choice { values: &'static [&'static str] },
+    /// Three channels in `0..=max`, written as "r,g,b"
+    Rgb { max: u8 },
+}
+
+/// Description of a single setting
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingDef {
+    /// Field name in `MonitorSettings` as seen by the frontend
+    pub field: &'static str,
+    /// msigd flag and `--query` key
+    pub flag: &'static str,
+    /// Human-readable name used in messages
+    pub label: &'static str,
+    pub kind: SettingKind,
+}
+
+const fn numeric(
+    field: &'static str,
+    flag: &'static str,
+    label: &'static str,
+    max: u8,
+) -> SettingDef {
+    SettingDef {
+        field,
+        flag,
+        label,
+        kind: SettingKind::Numeric { min: 0, max },
+    }
+}
+
+const fn toggle(field: &'static str, flag: &'static str, label: &'static str) -> SettingDef {
+    SettingDef {
+        field,
+        flag,
+        label,
+        kind: SettingKind::Toggle,
+    }
+}
+
+const fn choice(
+    field: &'static str,
+    flag: &'static str,
+    label: &'static str,
+    values: &'static [&'static str],
+) -> SettingDef {
+    SettingDef {
+        field,
+        flag,
+        label,
+        kind: SettingKind::Choice { values },
+    }
+}
+
+const POSITIONS: &[&str] = &["left_top", "right_top", "left_bottom", "right_bottom"];
+
+/// Every setting in `MonitorSettings`, in display order
+pub const SETTINGS: &[SettingDef] = &[
+    numeric("brightness", "brightness", "Brightness", 100),
+    numeric("contrast", "contrast", "Contrast", 100),
+    numeric("sharpness", "sharpness", "Sharpness", 5),
+    choice("responseTime", "response_time", "Response time", &["normal", "fast", "fastest"]),
+    toggle("eyeSaver", "eye_saver", "Eye saver"),
+    choice(
+        "imageEnhancement",
+        "image_enhancement",
+        "Image enhancement",
+        &["off", "weak", "medium", "strong", "strongest"],
+    ),
+    choice("colorPreset", "color_preset", "Color preset", &["cool", "normal", "warm", "custom"]),
+    SettingDef {
+        field: "colorRgb",
+        flag: "color_rgb",
+        label: "RGB values",
+        kind: SettingKind::Rgb { max: 100 },
+    },
+    toggle("hdcr", "hdcr", "HDCR"),
+    // msigd uses "refresh_display" not "refresh_rate_display"
+    toggle("refreshRateDisplay", "refresh_display", "Refresh rate display"),
+    // Phase 1: OSD settings
+    numeric("osdTransparency", "osd_transparency", "OSD transparency", 5),
+    numeric("osdTimeout", "osd_timeout", "OSD timeout", 30),
+    // Phase 2: MAG Core settings
+    choice(
+        "nightVision",
+        "night_vision",
+        "Night vision",
+        &["off", "normal", "strong", "strongest", "ai"],
+    ),
+    numeric("blackTuner", "black_tuner", "Black tuner", 20),
+    choice(
+        "screenAssistance",
+        "screen_assistance",
+        "Screen assistance",
+        &[
+            "off", "red1", "red2", "red3", "red4", "red5", "red6",
+            "white1", "white2", "white3", "white4", "white5", "white6",
+        ],
+    ),
+    choice("refreshPosition", "refresh_position", "Refresh position", POSITIONS),
+    choice("alarmClock", "alarm_clock", "Alarm clock", &["off", "1", "2", "3", "4"]),
+    choice("alarmPosition", "alarm_position", "Alarm position", POSITIONS),
+    toggle("soundEnable", "sound_enable", "Sound"),
+    // Phase 3: Performance settings
+    toggle("zeroLatency", "zero_latency", "Zero latency"),
+    toggle("freeSync", "free_sync", "FreeSync"),
+    choice(
+        "gameMode",
+        "game_mode",
+        "Game mode",
+        &["user", "fps", "racing", "rts", "rpg", "premium_color"],
+    ),
+    choice(
+        "proMode",
+        "pro_mode",
+        "Pro mode",
+        &[
+            "user", "reader", "cinema", "designer", "office", "srgb",
+            "adobe_rgb", "dci_p3", "eco", "anti_blue", "movie",
+        ],
+    ),
+    // Phase 4: Input/System settings
+    choice("input", "input", "Input", &["hdmi1", "hdmi2", "dp", "usbc"]),
+    toggle("autoScan", "auto_scan", "Auto scan"),
+    toggle("screenInfo", "screen_info", "Screen info"),
+    choice(
+        "screenSize",
+        "screen_size",
+        "Screen size",
+        &["auto", "4:3", "16:9", "21:9", "1:1", "19", "24"],
+    ),
+    choice("powerButton", "power_button", "Power button", &["off", "standby"]),
+    toggle("hdmiCec", "hdmi_cec", "HDMI CEC"),
+    choice("kvm", "kvm", "KVM", &["auto", "upstream", "type_c"]),
+    choice("audioSource", "audio_source", "Audio source", &["analog", "digital"]),
+    toggle("rgbLed", "rgb_led", "RGB LED"),
+];
+
+/// Look up a setting by msigd flag
+pub fn by_flag(flag: &str) -> Option<&'static SettingDef> {
+    SETTINGS.iter().find(|d| d.flag == flag)
+}
+
+/// Look up a setting by msigd flag or `MonitorSettings` field name
+pub fn lookup(name: &str) -> Option<&'static SettingDef> {
+    SETTINGS.iter().find(|d| d.flag == name || d.field == name)
+}
+
+impl SettingDef {
+    /// Validate a value given in msigd syntax, returning it normalized
+    pub fn validate(&self, value: &str) -> Result<String, MsigdError> {
+        let invalid = || MsigdError::InvalidValue(self.expected());
+        match self.kind {
+            SettingKind::Numeric { min, max } => {
+                let v: u8 = value.parse().map_err(|_| invalid())?;
+                (min..=max).contains(&v).then(|| v.to_string()).ok_or_else(invalid)
+            }
+            SettingKind::Toggle => match value {
+                "on" | "off" => Ok(value.to_string()),
+                _ => Err(invalid()),
+            },
+            SettingKind::Choice { values } => values
+                .contains(&value)
+                .then(|| value.to_string())
+                .ok_or_else(invalid),
+            SettingKind::Rgb { max } => {
+                let channels: Vec<u8> = value
+                    .split(',')
+                    .map(|c| c.trim().parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+                match channels[..] {
+                    [r, g, b] if r <= max && g <= max && b <= max => {
+                        Ok(format!("{},{},{}", r, g, b))
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+
+    /// Convert a serialized `MonitorSettings` field to msigd syntax
+    pub fn msigd_value(&self, value: &Value) -> Result<String, MsigdError> {
+        let invalid = || MsigdError::InvalidValue(self.expected());
+        let text = match (self.kind, value) {
+            (SettingKind::Numeric { .. }, Value::Number(n)) => n.to_string(),
+            (SettingKind::Toggle, Value::Bool(b)) => if *b { "on" } else { "off" }.to_string(),
+            (SettingKind::Choice { .. }, Value::String(s)) => s.clone(),
+            (SettingKind::Rgb { .. }, Value::Object(rgb)) => ["r", "g", "b"]
+                .iter()
+                .map(|c| rgb.get(*c).map(Value::to_string).ok_or_else(invalid))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(","),
+            _ => return Err(invalid()),
+        };
+        self.validate(&text)
+    }
+
+    /// Convert a value from msigd output (names or numeric codes) to the
+    /// serialized `MonitorSettings` field
+    pub fn parse_value(&self, raw: &str) -> Option<Value> {
+        let raw = raw.trim();
+        match self.kind {
+            SettingKind::Numeric { .. } => raw.parse::<u8>().ok().map(|v| json!(v)),
+            SettingKind::Toggle => match raw {
+                "on" | "1" | "true" => Some(json!(true)),
+                "off" | "0" | "false" => Some(json!(false)),
+                _ => None,
+            },
+            SettingKind::Choice { values } => values
+                .iter()
+                .find(|v| **v == raw)
+                .or_else(|| raw.parse::<usize>().ok().and_then(|code| values.get(code)))
+                .map(|v| json!(v)),
+            SettingKind::Rgb { .. } => {
+                let channels: Vec<u8> = raw
+                    .split([',', ':'])
+                    .filter_map(|c| c.trim().parse().ok())
+                    .collect();
+                match channels[..] {
+                    [r, g, b] => Some(json!({ "r": r, "g": g, "b": b })),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Message describing the accepted values
+    fn expected(&self) -> String {
+        match self.kind {
+            SettingKind::Numeric { min, max } => format!("{} must be {}-{}", self.label, min, max),
+            SettingKind::Toggle => format!("{} must be on or off", self.label),
+            SettingKind::Choice { values } => {
+                format!("{} must be one of: {}", self.label, values.join(", "))
+            }
+            SettingKind::Rgb { max } => format!("{} must be 0-{}", self.label, max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::types::{ColorRgb, MonitorSettings, SettingValue};
+
+    #[test]
+    fn test_registry_covers_monitor_settings() {
+        let defaults = serde_json::to_value(MonitorSettings::default()).unwrap();
+        let fields = defaults.as_object().unwrap();
+
+        assert_eq!(fields.len(), SETTINGS.len());
+        for def in SETTINGS {
+            let value = &fields[def.field];
+            assert!(def.msigd_value(value).is_ok(), "default of {} is invalid", def.field);
+        }
+    }
+
+    #[test]
+    fn test_choice_values_match_serde_names() {
+        for def in SETTINGS {
+            let SettingKind::Choice { values } = def.kind else {
+                continue;
+            };
+            for value in values {
+                let mut settings = serde_json::to_value(MonitorSettings::default()).unwrap();
+                settings[def.field] = json!(value);
+                let parsed: MonitorSettings = serde_json::from_value(settings)
+                    .unwrap_or_else(|_| panic!("{} rejects {}", def.field, value));
+                assert_eq!(serde_json::to_value(parsed).unwrap()[def.field], json!(value));
+            }
+        }
+    }
+
+    #[test]
+    fn test_setting_value_covers_registry() {
+        let defaults = serde_json::to_value(MonitorSettings::default()).unwrap();
+        for def in SETTINGS {
+            let tagged = json!({ "setting": def.field, "value": defaults[def.field] });
+            let setting: SettingValue = serde_json::from_value(tagged)
+                .unwrap_or_else(|_| panic!("no SettingValue variant for {}", def.field));
+            assert_eq!(setting.to_msigd().unwrap().0.flag, def.flag);
+        }
+
+        let setting = SettingValue::parse("refresh_display", "on").unwrap();
+        assert_eq!(
+            serde_json::to_value(&setting).unwrap(),
+            json!({ "setting": "refreshRateDisplay", "value": true })
+        );
+        assert!(SettingValue::parse("screen_size", "3").is_err());
+        let error = SettingValue::ColorRgb(ColorRgb { r: 0, g: 101, b: 0 })
+            .to_msigd()
+            .unwrap_err();
+        assert!(matches!(error.root(), MsigdError::InvalidValue(_)));
+        assert_eq!(serde_json::to_value(&error).unwrap()["setting"], "color_rgb");
+    }
+
+    #[test]
+    fn test_validate() {
+        let brightness = by_flag("brightness").unwrap();
+        assert_eq!(brightness.validate("40").unwrap(), "40");
+        assert!(brightness.validate("101").is_err());
+
+        let kvm = lookup("kvm").unwrap();
+        assert!(kvm.validate("type_c").is_ok());
+        let expected = "KVM must be one of: auto, upstream, type_c";
+        assert!(matches!(
+            kvm.validate("usb"),
+            Err(MsigdError::InvalidValue(msg)) if msg == expected
+        ));
+
+        let rgb = lookup("colorRgb").unwrap();
+        assert_eq!(rgb.validate("10, 20,30").unwrap(), "10,20,30");
+        assert!(rgb.validate("10,20").is_err());
+        assert!(rgb.validate("10,20,300").is_err());
+    }
+
+    #[test]
+    fn test_parse_value_accepts_numeric_codes() {
+        let screen_size = by_flag("screen_size").unwrap();
+        assert_eq!(screen_size.parse_value("3"), Some(json!("21:9")));
+        assert_eq!(screen_size.parse_value("16:9"), Some(json!("16:9")));
+        assert_eq!(screen_size.parse_value("9"), None);
+        assert_eq!(by_flag("alarm_clock").unwrap().parse_value("2"), Some(json!("2")));
+        assert_eq!(by_flag("hdcr").unwrap().parse_value("1"), Some(json!(true)));
+    }
+}
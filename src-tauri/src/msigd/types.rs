@@ -1,15 +1,31 @@
 //! Data types for msigd CLI output
 
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::MsigdError;
+use crate::msigd::mystic::LastKnownMysticLight;
+use crate::msigd::registry::{self, SettingDef};
 
 /// Represents a connected MSI monitor
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Monitor {
+    /// Stable identifier that survives re-enumeration, see `identity::assign_ids`
     pub id: String,
+    /// msigd index, which can change when monitors are replugged
+    pub index: String,
+    /// Friendly name set by the user
+    pub name: Option<String>,
     pub serial: String,
+    /// Vendor code from --list, e.g. "MS"
+    pub vendor: String,
     pub model: String,
     pub firmware: String,
+    /// hidraw node msigd uses for this monitor, e.g. "/dev/hidraw4"
+    pub device_path: Option<String>,
+    /// Serial plus device path, unique even for identical monitors
+    pub identity: String,
 }
 
 // Original enums
@@ -40,7 +56,7 @@ pub enum ColorPreset {
     Custom,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ColorRgb {
     pub r: u8,
@@ -283,29 +299,214 @@ impl Default for MonitorSettings {
     }
 }
 
-#[allow(dead_code)]
+/// Settings returned by a query, with the fields the monitor reported
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct MysticLightConfig {
-    pub led_group: String,
-    pub mode: MysticLightMode,
-    pub colors: Vec<String>,
+pub struct QueriedSettings {
+    #[serde(flatten)]
+    pub settings: MonitorSettings,
+    /// `MonitorSettings` fields present in the msigd output; the others hold
+    /// defaults and are not supported by the monitor
+    pub reported: Vec<String>,
+    /// Mystic Light config from the last successful write; LED state cannot
+    /// be read from the monitor
+    #[serde(default)]
+    pub last_known_mystic_light: Option<LastKnownMysticLight>,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum MysticLightMode {
-    Off,
-    Static,
-    Breathing,
-    Blinking,
-    Flashing,
-    #[serde(rename = "double_flashing")]
-    DoubleFlashing,
-    Lightning,
-    Rainbow,
-    Meteor,
-    #[serde(rename = "rainbow_gradient")]
-    RainbowGradient,
+impl QueriedSettings {
+    /// The reported settings only, leaving out unsupported ones
+    pub fn reported_settings(&self) -> PartialMonitorSettings {
+        let mut fields = serde_json::to_value(&self.settings).unwrap_or_default();
+        if let Some(fields) = fields.as_object_mut() {
+            fields.retain(|field, _| self.reported.contains(field));
+        }
+        serde_json::from_value(fields).unwrap_or_default()
+    }
+}
+
+/// Subset of monitor settings, used for batch writes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialMonitorSettings {
+    pub brightness: Option<u8>,
+    pub contrast: Option<u8>,
+    pub sharpness: Option<u8>,
+    pub response_time: Option<ResponseTime>,
+    pub eye_saver: Option<bool>,
+    pub image_enhancement: Option<ImageEnhancement>,
+    pub color_preset: Option<ColorPreset>,
+    pub color_rgb: Option<ColorRgb>,
+    pub hdcr: Option<bool>,
+    pub refresh_rate_display: Option<bool>,
+    // Phase 1: OSD settings
+    pub osd_transparency: Option<u8>,
+    pub osd_timeout: Option<u8>,
+    // Phase 2: MAG Core settings
+    pub night_vision: Option<NightVision>,
+    pub black_tuner: Option<u8>,
+    pub screen_assistance: Option<ScreenAssistance>,
+    pub refresh_position: Option<Position>,
+    pub alarm_clock: Option<AlarmClock>,
+    pub alarm_position: Option<Position>,
+    pub sound_enable: Option<bool>,
+    // Phase 3: Performance settings
+    pub zero_latency: Option<bool>,
+    pub free_sync: Option<bool>,
+    pub game_mode: Option<GameMode>,
+    pub pro_mode: Option<ProMode>,
+    // Phase 4: Input/System settings
+    pub input: Option<InputSource>,
+    pub auto_scan: Option<bool>,
+    pub screen_info: Option<bool>,
+    pub screen_size: Option<ScreenSize>,
+    pub power_button: Option<PowerButton>,
+    pub hdmi_cec: Option<bool>,
+    pub kvm: Option<KvmMode>,
+    pub audio_source: Option<AudioSource>,
+    pub rgb_led: Option<bool>,
+}
+
+impl From<MonitorSettings> for PartialMonitorSettings {
+    fn from(s: MonitorSettings) -> Self {
+        Self {
+            brightness: Some(s.brightness),
+            contrast: Some(s.contrast),
+            sharpness: Some(s.sharpness),
+            response_time: Some(s.response_time),
+            eye_saver: Some(s.eye_saver),
+            image_enhancement: Some(s.image_enhancement),
+            color_preset: Some(s.color_preset),
+            color_rgb: Some(s.color_rgb),
+            hdcr: Some(s.hdcr),
+            refresh_rate_display: Some(s.refresh_rate_display),
+            osd_transparency: Some(s.osd_transparency),
+            osd_timeout: Some(s.osd_timeout),
+            night_vision: Some(s.night_vision),
+            black_tuner: Some(s.black_tuner),
+            screen_assistance: Some(s.screen_assistance),
+            refresh_position: Some(s.refresh_position),
+            alarm_clock: Some(s.alarm_clock),
+            alarm_position: Some(s.alarm_position),
+            sound_enable: Some(s.sound_enable),
+            zero_latency: Some(s.zero_latency),
+            free_sync: Some(s.free_sync),
+            game_mode: Some(s.game_mode),
+            pro_mode: Some(s.pro_mode),
+            input: Some(s.input),
+            auto_scan: Some(s.auto_scan),
+            screen_info: Some(s.screen_info),
+            screen_size: Some(s.screen_size),
+            power_button: Some(s.power_button),
+            hdmi_cec: Some(s.hdmi_cec),
+            kvm: Some(s.kvm),
+            audio_source: Some(s.audio_source),
+            rgb_led: Some(s.rgb_led),
+        }
+    }
+}
+
+/// Outcome of writing a single field in a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldResult {
+    pub field: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// `MsigdError::code` of the failure
+    #[serde(default)]
+    pub error_code: Option<String>,
+}
+
+impl FieldResult {
+    pub fn ok(field: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            success: true,
+            error: None,
+            error_code: None,
+        }
+    }
+
+    pub fn failed(field: &str, error: &MsigdError) -> Self {
+        Self {
+            field: field.to_string(),
+            success: false,
+            error: Some(error.to_string()),
+            error_code: Some(error.code().to_string()),
+        }
+    }
+}
+
+/// A single setting and its new value
+///
+/// Serialized as `{ "setting": "<field>", "value": ... }` using the same
+/// field names as `MonitorSettings`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "setting", content = "value", rename_all = "camelCase")]
+pub enum SettingValue {
+    Brightness(u8),
+    Contrast(u8),
+    Sharpness(u8),
+    ResponseTime(ResponseTime),
+    EyeSaver(bool),
+    ImageEnhancement(ImageEnhancement),
+    ColorPreset(ColorPreset),
+    ColorRgb(ColorRgb),
+    Hdcr(bool),
+    RefreshRateDisplay(bool),
+    // Phase 1: OSD settings
+    OsdTransparency(u8),
+    OsdTimeout(u8),
+    // Phase 2: MAG Core settings
+    NightVision(NightVision),
+    BlackTuner(u8),
+    ScreenAssistance(ScreenAssistance),
+    RefreshPosition(Position),
+    AlarmClock(AlarmClock),
+    AlarmPosition(Position),
+    SoundEnable(bool),
+    // Phase 3: Performance settings
+    ZeroLatency(bool),
+    FreeSync(bool),
+    GameMode(GameMode),
+    ProMode(ProMode),
+    // Phase 4: Input/System settings
+    Input(InputSource),
+    AutoScan(bool),
+    ScreenInfo(bool),
+    ScreenSize(ScreenSize),
+    PowerButton(PowerButton),
+    HdmiCec(bool),
+    Kvm(KvmMode),
+    AudioSource(AudioSource),
+    RgbLed(bool),
+}
+
+impl SettingValue {
+    /// Build from an msigd flag and a value in msigd syntax
+    pub fn parse(flag: &str, value: &str) -> Result<Self, MsigdError> {
+        let def = registry::by_flag(flag)
+            .ok_or_else(|| MsigdError::InvalidValue(format!("Unknown setting: {}", flag)))?;
+        let value = def.validate(value).map_err(|e| e.with_setting(flag))?;
+        let value = def
+            .parse_value(&value)
+            .ok_or_else(|| MsigdError::InvalidValue(format!("Unknown setting: {}", flag)))?;
+        serde_json::from_value(json!({ "setting": def.field, "value": value }))
+            .map_err(|e| MsigdError::InvalidValue(e.to_string()))
+    }
+
+    /// Registry entry and validated msigd value for this setting
+    pub fn to_msigd(&self) -> Result<(&'static SettingDef, String), MsigdError> {
+        let tagged =
+            serde_json::to_value(self).map_err(|e| MsigdError::ParseError(e.to_string()))?;
+        let def = tagged["setting"]
+            .as_str()
+            .and_then(registry::lookup)
+            .ok_or_else(|| MsigdError::InvalidValue(format!("Unknown setting: {}", tagged)))?;
+        let value = def
+            .msigd_value(&tagged["value"])
+            .map_err(|e| e.with_setting(def.flag))?;
+        Ok((def, value))
+    }
 }
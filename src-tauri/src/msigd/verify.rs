@@ -0,0 +1,199 @@
+//! Read-back verification of writes
+//!
+//! msigd reports success for writes the monitor silently ignores. In verify
+//! mode every written setting is queried again and compared with the value
+//! sent; mismatches are re-sent up to the configured number of retries and
+//! then reported as [`MsigdError::VerifyFailed`].
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{MonitorBackend, SharedBackend};
+use crate::msigd::parser::MsigdParser;
+use crate::msigd::registry;
+
+/// Default wait between a write and its read-back
+pub const DEFAULT_SETTLE: Duration = Duration::from_millis(100);
+
+/// Backend wrapper that re-queries settings after writing them
+pub struct VerifiedWrites {
+    inner: SharedBackend,
+    retries: u32,
+    settle: Duration,
+}
+
+impl VerifiedWrites {
+    /// Verify every write, re-sending mismatched settings up to `retries` times
+    pub fn new(inner: SharedBackend, retries: u32) -> Self {
+        Self {
+            inner,
+            retries,
+            settle: DEFAULT_SETTLE,
+        }
+    }
+
+    /// Override the wait between a write and its read-back
+    #[cfg(test)]
+    pub fn with_settle(mut self, settle: Duration) -> Self {
+        self.settle = settle;
+        self
+    }
+
+    /// Settings whose read-back differs from the written value, with the
+    /// value the monitor reports
+    ///
+    /// Settings the monitor does not report cannot be verified and are
+    /// treated as applied.
+    async fn mismatches(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<Vec<(String, String, String)>, MsigdError> {
+        tokio::time::sleep(self.settle).await;
+        let queried = MsigdParser::parse_query(&self.inner.query(monitor_id).await?)?;
+        let current = serde_json::to_value(&queried.settings)
+            .map_err(|e| MsigdError::ParseError(e.to_string()))?;
+
+        Ok(settings
+            .iter()
+            .filter_map(|(setting, value)| {
+                let def = registry::by_flag(setting)?;
+                if !queried.reported.iter().any(|f| f == def.field) {
+                    return None;
+                }
+                let actual = &current[def.field];
+                (def.parse_value(value).as_ref() != Some(actual)).then(|| {
+                    let actual = def.msigd_value(actual).unwrap_or_else(|_| actual.to_string());
+                    (setting.clone(), value.clone(), actual)
+                })
+            })
+            .collect())
+    }
+
+    /// Write `settings`, read them back and retry the ones that did not stick
+    async fn write_verified(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        let mut output = self.inner.set_many(monitor_id, settings).await?;
+        let mut pending = settings.to_vec();
+        for attempt in 0..=self.retries {
+            let mismatches = self.mismatches(monitor_id, &pending).await?;
+            let Some((setting, expected, actual)) = mismatches.first().cloned() else {
+                return Ok(output);
+            };
+            if attempt == self.retries {
+                return Err(MsigdError::VerifyFailed { expected, actual }.with_setting(&setting));
+            }
+            pending = mismatches
+                .into_iter()
+                .map(|(setting, value, _)| (setting, value))
+                .collect();
+            output = self.inner.set_many(monitor_id, &pending).await?;
+        }
+        Ok(output)
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for VerifiedWrites {
+    async fn list(&self) -> Result<String, MsigdError> {
+        self.inner.list().await
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.inner.query(monitor_id).await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        self.write_verified(monitor_id, &[(setting.to_string(), value.to_string())])
+            .await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        self.write_verified(monitor_id, settings).await
+    }
+
+    /// Mystic Light cannot be read back, so it is written unverified
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.inner.mystic(monitor_id, config).await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        self.inner.invalidate(monitor_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+
+    fn verified(monitor: MockMonitor, retries: u32) -> (Arc<MockBackend>, VerifiedWrites) {
+        let mock = Arc::new(MockBackend::new(vec![monitor]));
+        let backend = VerifiedWrites::new(mock.clone(), retries).with_settle(Duration::ZERO);
+        (mock, backend)
+    }
+
+    #[tokio::test]
+    async fn test_retries_ignored_writes() {
+        let (mock, backend) = verified(MockMonitor::new("SN1", "MAG274QRF-QD").ignoring(1), 1);
+
+        backend.set("1", "brightness", "80").await.unwrap();
+
+        assert_eq!(mock.writes().len(), 2);
+        assert_eq!(mock.monitor("1").unwrap().settings.brightness, 80);
+    }
+
+    #[tokio::test]
+    async fn test_reports_mismatch_after_retries() {
+        let (mock, backend) = verified(MockMonitor::new("SN1", "MAG274QRF-QD").ignoring(3), 1);
+        let settings = [
+            ("eye_saver".to_string(), "on".to_string()),
+            ("response_time".to_string(), "fastest".to_string()),
+        ];
+
+        let error = backend.set_many("1", &settings).await.unwrap_err();
+
+        assert_eq!(error.code(), "verify_failed");
+        assert!(matches!(
+            error.root(),
+            MsigdError::VerifyFailed { expected, actual } if expected == "on" && actual == "off"
+        ));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap()["setting"],
+            "eye_saver"
+        );
+        // Both settings were re-sent once before giving up
+        assert_eq!(mock.writes().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_unreported_settings_are_not_verified() {
+        let monitor = MockMonitor::new("SN1", "MAG274QRF-QD")
+            .without(&["kvm"])
+            .ignoring(1);
+        let (mock, backend) = verified(monitor, 0);
+
+        backend.set("1", "kvm", "upstream").await.unwrap();
+        assert_eq!(mock.writes().len(), 1);
+    }
+}
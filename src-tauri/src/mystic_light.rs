@@ -0,0 +1,202 @@
+//! Last applied Mystic Light config per monitor, persisted in the app config dir
+//!
+//! msigd cannot read LED state back from the monitor, so the config from the
+//! last successful Mystic Light write is what the LED tab shows. Writes are
+//! recorded by [`MysticLightRecorder`], which sees every write whether it came
+//! from the LED tab, a profile, restore on reconnect or automation.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{MonitorBackend, SharedBackend};
+use crate::msigd::identity::StableIdLookup;
+use crate::msigd::mystic::LastKnownMysticLight;
+use crate::msigd::{MysticLightConfig, QueriedSettings};
+use crate::profiles::write_json;
+
+/// File name of the Mystic Light store inside the app config dir
+pub const MYSTIC_LIGHT_FILE: &str = "mystic_light.json";
+
+const STORE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    /// Last applied config by stable monitor id
+    monitors: BTreeMap<String, LastKnownMysticLight>,
+}
+
+/// Last applied Mystic Light configs kept in memory and mirrored to a JSON file
+pub struct MysticLightStore {
+    path: PathBuf,
+    monitors: Mutex<BTreeMap<String, LastKnownMysticLight>>,
+}
+
+impl MysticLightStore {
+    /// Load configs from `path`, starting empty if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let monitors = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.monitors)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            monitors: Mutex::new(monitors),
+        }
+    }
+
+    pub fn get(&self, monitor_id: &str) -> Option<LastKnownMysticLight> {
+        self.monitors.lock().unwrap().get(monitor_id).cloned()
+    }
+
+    /// Remember a config that was successfully written to a monitor
+    pub fn record(&self, monitor_id: &str, config: MysticLightConfig) -> Result<(), MsigdError> {
+        let mut monitors = self.monitors.lock().unwrap();
+        let applied_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        monitors.insert(
+            monitor_id.to_string(),
+            LastKnownMysticLight { config, applied_at },
+        );
+        let file = StoreFile {
+            version: STORE_VERSION,
+            monitors: monitors.clone(),
+        };
+        write_json(&self.path, &file)
+    }
+
+    /// Add the last known config to settings queried from a monitor
+    pub fn merge(&self, monitor_id: &str, settings: &mut QueriedSettings) {
+        settings.last_known_mystic_light = self.get(monitor_id);
+    }
+}
+
+/// Backend wrapper that remembers successful Mystic Light writes in a
+/// [`MysticLightStore`], keyed by stable id
+pub struct MysticLightRecorder {
+    inner: SharedBackend,
+    store: Arc<MysticLightStore>,
+    ids: StableIdLookup,
+}
+
+impl MysticLightRecorder {
+    pub fn new(inner: SharedBackend, store: Arc<MysticLightStore>) -> Self {
+        Self {
+            inner,
+            store,
+            ids: StableIdLookup::default(),
+        }
+    }
+
+    async fn remember(&self, monitor_id: &str, config: &str) -> Result<(), MsigdError> {
+        let id = self
+            .ids
+            .resolve(self.inner.as_ref(), monitor_id)
+            .await
+            .ok_or_else(|| MsigdError::MonitorNotFound(monitor_id.to_string()))?;
+        self.store.record(&id, MysticLightConfig::from_msigd(config)?)
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for MysticLightRecorder {
+    async fn list(&self) -> Result<String, MsigdError> {
+        let output = self.inner.list().await?;
+        self.ids.remember(&output);
+        Ok(output)
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.inner.query(monitor_id).await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        self.inner.set(monitor_id, setting, value).await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        self.inner.set_many(monitor_id, settings).await
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        let output = self.inner.mystic(monitor_id, config).await?;
+        // A failed save must not turn a successful write into an error
+        if let Err(e) = self.remember(monitor_id, config).await {
+            eprintln!("Failed to remember Mystic Light config: {}", e);
+        }
+        Ok(output)
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        self.inner.invalidate(monitor_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::identity::StableIds;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::mystic::MysticLightMode;
+    use crate::msigd::{MsigdParser, PartialMonitorSettings};
+    use crate::profiles::Profile;
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn test_merges_last_known_config_after_reload() {
+        let dir = TempDir::new("mystic");
+        let path = dir.join(MYSTIC_LIGHT_FILE);
+        let config = MysticLightConfig {
+            led_group: "0".to_string(),
+            mode: MysticLightMode::Breathing,
+            colors: vec!["#ff0000".to_string(), "#0000ff".to_string()],
+        };
+        let mock = Arc::new(MockBackend::new(vec![MockMonitor::new("SN1", "MAG274QRF-QD")]));
+        let recorder = MysticLightRecorder::new(
+            Arc::new(StableIds::new(mock)),
+            Arc::new(MysticLightStore::load(path.clone())),
+        );
+        // Written the way a profile apply does, by msigd index
+        let profile = Profile {
+            name: "Glow".to_string(),
+            settings: PartialMonitorSettings::default(),
+            mystic_light: Some(config.clone()),
+        };
+        assert!(profile.apply(&recorder, "1").await.iter().all(|r| r.success));
+
+        let store = MysticLightStore::load(path.clone());
+        let mut settings = MsigdParser::parse_query("brightness: 70").unwrap();
+        store.merge("SN1", &mut settings);
+        assert_eq!(settings.last_known_mystic_light.unwrap().config, config);
+
+        let mut other = MsigdParser::parse_query("brightness: 70").unwrap();
+        store.merge("SN2", &mut other);
+        let json = serde_json::to_value(&other).unwrap();
+        assert!(json["lastKnownMysticLight"].is_null());
+        assert_eq!(json["brightness"], 70);
+    }
+}
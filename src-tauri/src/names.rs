@@ -0,0 +1,96 @@
+//! Friendly monitor names persisted in the app config dir
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::MsigdError;
+use crate::msigd::Monitor;
+use crate::profiles::write_json;
+
+/// File name of the monitor name store inside the app config dir
+pub const NAMES_FILE: &str = "monitors.json";
+
+const STORE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    /// Friendly name by stable monitor id
+    names: BTreeMap<String, String>,
+}
+
+/// User-assigned monitor names kept in memory and mirrored to a JSON file
+pub struct MonitorNames {
+    path: PathBuf,
+    names: Mutex<BTreeMap<String, String>>,
+}
+
+impl MonitorNames {
+    /// Load names from `path`, starting empty if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let names = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.names)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            names: Mutex::new(names),
+        }
+    }
+
+    /// Fill in `name` on each monitor from its stable id
+    pub fn apply(&self, monitors: &mut [Monitor]) {
+        let names = self.names.lock().unwrap();
+        for monitor in monitors {
+            monitor.name = names.get(&monitor.id).cloned();
+        }
+    }
+
+    /// Name a monitor, or clear its name when `name` is empty
+    pub fn set(&self, monitor_id: &str, name: &str) -> Result<(), MsigdError> {
+        let mut names = self.names.lock().unwrap();
+        match name.trim() {
+            "" => names.remove(monitor_id),
+            name => names.insert(monitor_id.to_string(), name.to_string()),
+        };
+        let file = StoreFile {
+            version: STORE_VERSION,
+            names: names.clone(),
+        };
+        write_json(&self.path, &file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::backend;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn test_names_persist_by_stable_id() {
+        let dir = TempDir::new("names");
+        let path = dir.join(NAMES_FILE);
+        let names = MonitorNames::load(path.clone());
+        names.set("SN2", " Left ").unwrap();
+        names.set("SN1", "Right").unwrap();
+        names.set("SN1", "").unwrap();
+
+        let backend = MockBackend::new(vec![
+            MockMonitor::new("SN1", "MAG274QRF-QD"),
+            MockMonitor::new("SN2", "MAG274QRF-QD"),
+        ]);
+        let mut monitors = backend::monitors(&backend).await.unwrap();
+        MonitorNames::load(path.clone()).apply(&mut monitors);
+
+        assert_eq!(monitors[0].name, None);
+        assert_eq!(monitors[1].name.as_deref(), Some("Left"));
+    }
+}
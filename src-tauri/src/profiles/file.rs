@@ -0,0 +1,255 @@
+//! Portable profile files for sharing profiles between machines
+//!
+//! A profile file is a single TOML or JSON document, chosen by extension:
+//!
+//! ```toml
+//! formatVersion = 1
+//! model = "MAG274QRF-QD"      # optional, monitor the profile was made on
+//! serial = "A02019010700"     # optional
+//!
+//! [profile]
+//! name = "Calibrated"
+//!
+//! [profile.settings]
+//! brightness = 40
+//! colorPreset = "custom"
+//! colorRgb = { r = 48, g = 50, b = 46 }
+//! proMode = "srgb"
+//!
+//! [profile.mysticLight]              # optional
+//! ledGroup = "0"
+//! mode = "static"
+//! colors = ["#ff0000"]
+//! ```
+//!
+//! Setting keys and values match `MonitorSettings` as seen by the frontend;
+//! omitted settings are left untouched when the profile is applied.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::MsigdError;
+use crate::msigd::batch::SettingsBatch;
+use crate::msigd::Monitor;
+use crate::profiles::Profile;
+
+/// Current version of the profile file format
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Serialization used for a profile file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Toml,
+    Json,
+}
+
+impl FileFormat {
+    /// Pick the format from a `.toml` or `.json` extension
+    pub fn from_path(path: &Path) -> Result<Self, MsigdError> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Ok(Self::Toml),
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(Self::Json),
+            _ => Err(MsigdError::InvalidValue(
+                "Profile file must end in .toml or .json".to_string(),
+            )),
+        }
+    }
+}
+
+/// On-disk representation of an exported profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileFile {
+    pub format_version: u32,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub profile: Profile,
+}
+
+impl ProfileFile {
+    /// Wrap a profile, recording the monitor it was taken from
+    pub fn new(profile: Profile, monitor: Option<&Monitor>) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            model: monitor.map(|m| m.model.clone()),
+            serial: monitor.map(|m| m.serial.clone()),
+            profile,
+        }
+    }
+
+    pub fn to_string(&self, format: FileFormat) -> Result<String, MsigdError> {
+        match format {
+            FileFormat::Toml => {
+                toml::to_string_pretty(self).map_err(|e| MsigdError::ParseError(e.to_string()))
+            }
+            FileFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| MsigdError::ParseError(e.to_string())),
+        }
+    }
+
+    /// Parse and validate a profile file
+    pub fn parse(contents: &str, format: FileFormat) -> Result<Self, MsigdError> {
+        let file: Self = match format {
+            FileFormat::Toml => {
+                toml::from_str(contents).map_err(|e| MsigdError::ParseError(e.to_string()))?
+            }
+            FileFormat::Json => serde_json::from_str(contents)
+                .map_err(|e| MsigdError::ParseError(e.to_string()))?,
+        };
+        file.validate()?;
+        Ok(file)
+    }
+
+    /// Check the format version, every setting against the allowed ranges and
+    /// the Mystic Light config
+    pub fn validate(&self) -> Result<(), MsigdError> {
+        if self.format_version == 0 || self.format_version > FORMAT_VERSION {
+            return Err(MsigdError::InvalidValue(format!(
+                "Unsupported profile format version {} (expected {})",
+                self.format_version, FORMAT_VERSION
+            )));
+        }
+        if self.profile.name.trim().is_empty() {
+            return Err(MsigdError::InvalidValue(
+                "Profile name must not be empty".to_string(),
+            ));
+        }
+
+        let batch = SettingsBatch::from_partial(&self.profile.settings);
+        let errors: Vec<String> = batch
+            .rejected()
+            .iter()
+            .filter_map(|r| r.error.clone())
+            .collect();
+        if !errors.is_empty() {
+            return Err(MsigdError::InvalidValue(errors.join("; ")));
+        }
+        if let Some(config) = &self.profile.mystic_light {
+            config.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Warning shown when importing onto a different monitor model
+    pub fn model_warning(&self, target: &Monitor) -> Option<String> {
+        let model = self.model.as_deref()?;
+        (model != target.model).then(|| {
+            format!(
+                "Profile was created on {} but monitor {} is {}",
+                model, target.id, target.model
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::{ColorPreset, ColorRgb, PartialMonitorSettings, ProMode};
+
+    fn monitor(model: &str) -> Monitor {
+        Monitor {
+            id: "1".to_string(),
+            index: "1".to_string(),
+            name: None,
+            serial: "A02019010700".to_string(),
+            vendor: "MS".to_string(),
+            model: model.to_string(),
+            firmware: "Unknown".to_string(),
+            device_path: None,
+            identity: "SN1".to_string(),
+        }
+    }
+
+    fn calibrated() -> Profile {
+        Profile {
+            name: "Calibrated".to_string(),
+            settings: PartialMonitorSettings {
+                brightness: Some(40),
+                color_preset: Some(ColorPreset::Custom),
+                color_rgb: Some(ColorRgb { r: 48, g: 50, b: 46 }),
+                pro_mode: Some(ProMode::Srgb),
+                ..Default::default()
+            },
+            mystic_light: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_toml_and_json() {
+        let file = ProfileFile::new(calibrated(), Some(&monitor("MAG274QRF-QD")));
+
+        for format in [FileFormat::Toml, FileFormat::Json] {
+            let text = file.to_string(format).unwrap();
+            let parsed = ProfileFile::parse(&text, format).unwrap();
+            assert_eq!(parsed.model.as_deref(), Some("MAG274QRF-QD"));
+            assert_eq!(parsed.profile.settings.brightness, Some(40));
+            assert_eq!(parsed.profile.settings.pro_mode, Some(ProMode::Srgb));
+            assert!(parsed.profile.settings.contrast.is_none());
+        }
+    }
+
+    #[test]
+    fn test_parse_documented_example() {
+        let text = r##"
+            formatVersion = 1
+            model = "MAG274QRF-QD"
+
+            [profile]
+            name = "Calibrated"
+
+            [profile.settings]
+            brightness = 40
+            colorRgb = { r = 48, g = 50, b = 46 }
+            proMode = "srgb"
+
+            [profile.mysticLight]
+            ledGroup = "0"
+            mode = "static"
+            colors = ["#ff0000"]
+        "##;
+
+        let file = ProfileFile::parse(text, FileFormat::Toml).unwrap();
+        assert_eq!(file.profile.name, "Calibrated");
+        assert_eq!(
+            file.profile.mystic_light.as_ref().unwrap().to_msigd().unwrap(),
+            "0:static:0xFF0000"
+        );
+        assert!(file.model_warning(&monitor("MAG274QRF-QD")).is_none());
+        assert!(file.model_warning(&monitor("MAG321CURV")).is_some());
+    }
+
+    #[test]
+    fn test_parse_rejects_out_of_range_values() {
+        let mut profile = calibrated();
+        profile.settings.black_tuner = Some(25);
+        let text = ProfileFile::new(profile, None)
+            .to_string(FileFormat::Json)
+            .unwrap();
+
+        let result = ProfileFile::parse(&text, FileFormat::Json);
+        assert!(matches!(result, Err(MsigdError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_mystic_light() {
+        let text = r##"{
+            "formatVersion": 1,
+            "profile": {
+                "name": "X",
+                "settings": {},
+                "mysticLight": {"ledGroup": "0", "mode": "static", "colors": []}
+            }
+        }"##;
+
+        let result = ProfileFile::parse(text, FileFormat::Json);
+        assert!(matches!(result, Err(MsigdError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn test_parse_rejects_newer_format() {
+        let text = r#"{"formatVersion": 2, "profile": {"name": "X", "settings": {}}}"#;
+        assert!(ProfileFile::parse(text, FileFormat::Json).is_err());
+    }
+}
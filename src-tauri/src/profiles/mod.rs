@@ -0,0 +1,278 @@
+//! Named settings profiles persisted in the app config dir
+
+pub mod file;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::batch::SettingsBatch;
+use crate::msigd::{FieldResult, MysticLightConfig, PartialMonitorSettings, QueriedSettings};
+
+/// File name of the profile store inside the app config dir
+pub const PROFILES_FILE: &str = "profiles.json";
+
+const STORE_VERSION: u32 = 1;
+
+/// A named set of monitor settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub name: String,
+    pub settings: PartialMonitorSettings,
+    pub mystic_light: Option<MysticLightConfig>,
+}
+
+impl Profile {
+    /// Build a profile from a monitor's current settings
+    ///
+    /// Settings the monitor did not report are left out, so the profile
+    /// applies cleanly to the same model.
+    pub fn from_settings(
+        name: &str,
+        queried: &QueriedSettings,
+        mystic_light: Option<MysticLightConfig>,
+    ) -> Self {
+        let mut settings = queried.reported_settings();
+        // Switching inputs from a profile would blank the screen
+        settings.input = None;
+        Self {
+            name: name.to_string(),
+            settings,
+            mystic_light: mystic_light.map(|config| config.normalized()),
+        }
+    }
+
+    /// Write the profile to a monitor, reporting the outcome per field
+    pub async fn apply(&self, backend: &dyn MonitorBackend, monitor_id: &str) -> Vec<FieldResult> {
+        let mut results = SettingsBatch::from_partial(&self.settings)
+            .apply(backend, monitor_id)
+            .await;
+
+        if let Some(config) = &self.mystic_light {
+            results.push(apply_mystic_light(backend, monitor_id, config).await);
+        }
+        results
+    }
+}
+
+/// Write a Mystic Light config, reported as the `mysticLight` field
+pub(crate) async fn apply_mystic_light(
+    backend: &dyn MonitorBackend,
+    monitor_id: &str,
+    config: &MysticLightConfig,
+) -> FieldResult {
+    let error = match config.to_msigd() {
+        Ok(config) => backend.mystic(monitor_id, &config).await.err(),
+        Err(e) => Some(e),
+    };
+    match error {
+        Some(e) => FieldResult::failed("mysticLight", &e),
+        None => FieldResult::ok("mysticLight"),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    profiles: Vec<Profile>,
+}
+
+/// Profiles kept in memory and mirrored to a JSON file
+pub struct ProfileStore {
+    path: PathBuf,
+    profiles: Mutex<Vec<Profile>>,
+}
+
+impl ProfileStore {
+    /// Load profiles from `path`, starting empty if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let profiles = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.profiles)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            profiles: Mutex::new(profiles),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Profile> {
+        self.profiles.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Profile, MsigdError> {
+        self.profiles
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| MsigdError::ProfileNotFound(name.to_string()))
+    }
+
+    /// Insert a profile, replacing any existing one with the same name
+    pub fn save(&self, profile: Profile) -> Result<(), MsigdError> {
+        let mut profiles = self.profiles.lock().unwrap();
+        match profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => profiles.push(profile),
+        }
+        self.persist(&profiles)
+    }
+
+    /// Insert a profile, failing if one with the same name exists
+    pub fn add(&self, profile: Profile) -> Result<(), MsigdError> {
+        let mut profiles = self.profiles.lock().unwrap();
+        if profiles.iter().any(|p| p.name == profile.name) {
+            return Err(MsigdError::ProfileExists(profile.name));
+        }
+        profiles.push(profile);
+        self.persist(&profiles)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), MsigdError> {
+        let mut profiles = self.profiles.lock().unwrap();
+        let len = profiles.len();
+        profiles.retain(|p| p.name != name);
+        if profiles.len() == len {
+            return Err(MsigdError::ProfileNotFound(name.to_string()));
+        }
+        self.persist(&profiles)
+    }
+
+    fn persist(&self, profiles: &[Profile]) -> Result<(), MsigdError> {
+        let file = StoreFile {
+            version: STORE_VERSION,
+            profiles: profiles.to_vec(),
+        };
+        write_json(&self.path, &file)
+    }
+}
+
+/// Write `value` as pretty JSON, replacing `path` atomically
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), MsigdError> {
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| MsigdError::StorageError(e.to_string()))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| MsigdError::StorageError(e.to_string()))?;
+    }
+    // Write to a temp file first so a crash never leaves a truncated store
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| MsigdError::StorageError(e.to_string()))?;
+    fs::rename(&tmp, path).map_err(|e| MsigdError::StorageError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::mystic::MysticLightMode;
+    use crate::msigd::MsigdParser;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_store_persists_profiles() {
+        let dir = TempDir::new("profiles");
+        let path = dir.join(PROFILES_FILE);
+        let store = ProfileStore::load(path.clone());
+        let coding = MsigdParser::parse_query("brightness: 30\ninput: dp").unwrap();
+        let movie = MsigdParser::parse_query("brightness: 80").unwrap();
+
+        store
+            .save(Profile::from_settings("Coding", &coding, None))
+            .unwrap();
+        store
+            .save(Profile::from_settings("Movie", &movie, None))
+            .unwrap();
+        store.delete("Movie").unwrap();
+        let duplicate = Profile::from_settings("Coding", &movie, None);
+        assert!(matches!(
+            store.add(duplicate),
+            Err(MsigdError::ProfileExists(_))
+        ));
+
+        let reloaded = ProfileStore::load(path.clone());
+        let profiles = reloaded.list();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].settings.brightness, Some(30));
+        assert!(profiles[0].settings.input.is_none());
+        assert!(matches!(
+            reloaded.delete("Movie"),
+            Err(MsigdError::ProfileNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_profile_from_monitor_skips_unreported_settings() {
+        let flags = ["sharpness", "kvm", "hdmi_cec"];
+        let model = || MockMonitor::new("SN1", "MAG274QRF-QD").without(&flags).read_only(&flags);
+        let backend = MockBackend::new(vec![model()]);
+        let queried = MsigdParser::parse_query(&backend.query("1").await.unwrap()).unwrap();
+
+        let profile = Profile::from_settings("Desk", &queried, None);
+        assert_eq!(profile.settings.brightness, Some(50));
+        assert!(profile.settings.sharpness.is_none());
+        assert!(profile.settings.kvm.is_none());
+        assert!(profile.settings.hdmi_cec.is_none());
+        assert!(profile.settings.input.is_none());
+
+        let other = MockBackend::new(vec![model()]);
+        let results = profile.apply(&other, "1").await;
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r.success), "{:?}", results);
+    }
+
+    #[tokio::test]
+    async fn test_apply_reports_rejected_fields() {
+        let backend = MockBackend::default();
+        let profile = Profile {
+            name: "Gaming".to_string(),
+            settings: PartialMonitorSettings {
+                brightness: Some(90),
+                sharpness: Some(9),
+                ..Default::default()
+            },
+            mystic_light: Some(MysticLightConfig {
+                led_group: "1".to_string(),
+                mode: MysticLightMode::Static,
+                colors: vec!["#ff0000".to_string()],
+            }),
+        };
+
+        let results = profile.apply(&backend, "1").await;
+
+        let rejected: Vec<_> = results.iter().filter(|r| !r.success).collect();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].field, "sharpness");
+        assert_eq!(backend.monitor("1").unwrap().settings.brightness, 90);
+        assert_eq!(
+            backend.monitor("1").unwrap().mystic.as_deref(),
+            Some("1:static:0xFF0000")
+        );
+
+        // Invalid configs are reported without reaching msigd
+        let invalid = Profile {
+            name: "Broken".to_string(),
+            settings: PartialMonitorSettings::default(),
+            mystic_light: Some(MysticLightConfig {
+                led_group: "0".to_string(),
+                mode: MysticLightMode::Static,
+                colors: vec!["red".to_string()],
+            }),
+        };
+        let results = invalid.apply(&backend, "1").await;
+        assert!(!results[0].success);
+        assert_eq!(
+            backend.monitor("1").unwrap().mystic.as_deref(),
+            Some("1:static:0xFF0000")
+        );
+    }
+}
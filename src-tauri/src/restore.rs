@@ -0,0 +1,396 @@
+//! Restore settings when a monitor reconnects
+//!
+//! Some settings, Mystic Light in particular, reset when a monitor is power
+//! cycled. When enabled, every successful write is remembered per monitor
+//! (keyed by stable id, normally the serial) and replayed in one batch when
+//! the hotplug watcher sees the monitor again.
+//!
+//! Transitions and background automation write many times a second, so
+//! changes are kept in memory and written to disk by [`spawn_flush`].
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{MonitorBackend, SharedBackend};
+use crate::msigd::identity::StableIdLookup;
+use crate::msigd::registry;
+use crate::msigd::{FieldResult, Monitor, MysticLightConfig, PartialMonitorSettings};
+use crate::profiles::{write_json, Profile};
+
+/// File name of the restore store inside the app config dir
+pub const RESTORE_FILE: &str = "restore.json";
+
+/// Number of restore reports kept in the log
+const LOG_SIZE: usize = 50;
+
+/// How often changed settings are written to disk
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+const STORE_VERSION: u32 = 1;
+
+/// Settings last written to a monitor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastApplied {
+    pub settings: PartialMonitorSettings,
+    pub mystic_light: Option<MysticLightConfig>,
+}
+
+/// Outcome of replaying settings onto a reconnected monitor
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    pub monitor_id: String,
+    pub model: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub results: Vec<FieldResult>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    enabled: bool,
+    monitors: BTreeMap<String, LastApplied>,
+}
+
+/// Last applied settings per monitor, mirrored to a JSON file
+pub struct RestoreStore {
+    path: PathBuf,
+    state: Mutex<StoreFile>,
+    log: Mutex<VecDeque<RestoreReport>>,
+    /// Whether remembered settings changed since the last write to disk
+    dirty: AtomicBool,
+}
+
+impl RestoreStore {
+    /// Load the store from `path`, starting disabled if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let state = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            state: Mutex::new(state),
+            log: Mutex::new(VecDeque::new()),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.state.lock().unwrap().enabled
+    }
+
+    /// Turn restore on reconnect on or off
+    ///
+    /// Remembered settings are dropped when disabling so stale values are
+    /// never replayed later.
+    pub fn set_enabled(&self, enabled: bool) -> Result<(), MsigdError> {
+        let mut state = self.state.lock().unwrap();
+        state.enabled = enabled;
+        if !enabled {
+            state.monitors.clear();
+        }
+        self.dirty.store(false, Ordering::SeqCst);
+        self.persist(&state)
+    }
+
+    pub fn last_applied(&self, monitor_id: &str) -> Option<LastApplied> {
+        self.state.lock().unwrap().monitors.get(monitor_id).cloned()
+    }
+
+    /// Restore reports, newest first
+    pub fn log(&self) -> Vec<RestoreReport> {
+        self.log.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Remember msigd (setting, value) pairs written to a monitor
+    fn record(&self, monitor_id: &str, settings: &[(String, String)]) -> Result<(), MsigdError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return Ok(());
+        }
+        let last = state.monitors.entry(monitor_id.to_string()).or_default();
+        let before = serde_json::to_value(&last.settings)
+            .map_err(|e| MsigdError::StorageError(e.to_string()))?;
+        let mut fields = before.clone();
+        for (setting, value) in settings {
+            // Switching inputs on reconnect would blank the screen
+            if setting == "input" {
+                continue;
+            }
+            if let Some(def) = registry::by_flag(setting) {
+                if let Some(value) = def.parse_value(value) {
+                    fields[def.field] = value;
+                }
+            }
+        }
+        if fields != before {
+            last.settings = serde_json::from_value(fields)
+                .map_err(|e| MsigdError::StorageError(e.to_string()))?;
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    fn record_mystic(&self, monitor_id: &str, config: &str) -> Result<(), MsigdError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return Ok(());
+        }
+        let config = MysticLightConfig::from_msigd(config)?;
+        let last = state.monitors.entry(monitor_id.to_string()).or_default();
+        if last.mystic_light.as_ref() != Some(&config) {
+            last.mystic_light = Some(config);
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    /// Write remembered settings to disk if they changed since the last write
+    pub fn flush(&self) -> Result<(), MsigdError> {
+        let state = self.state.lock().unwrap();
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let result = self.persist(&state);
+        if result.is_err() {
+            // Try again on the next flush
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// Replay the remembered settings onto a reconnected monitor
+    ///
+    /// Returns `None` when disabled or nothing was remembered.
+    pub async fn restore(
+        &self,
+        backend: &dyn MonitorBackend,
+        monitor: &Monitor,
+    ) -> Option<RestoreReport> {
+        if !self.enabled() {
+            return None;
+        }
+        let last = self.last_applied(&monitor.id)?;
+        let profile = Profile {
+            name: "Restore on reconnect".to_string(),
+            settings: last.settings,
+            mystic_light: last.mystic_light,
+        };
+
+        let report = RestoreReport {
+            monitor_id: monitor.id.clone(),
+            model: monitor.model.clone(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            results: profile.apply(backend, &monitor.id).await,
+        };
+        let mut log = self.log.lock().unwrap();
+        log.push_front(report.clone());
+        log.truncate(LOG_SIZE);
+        Some(report)
+    }
+
+    fn persist(&self, state: &StoreFile) -> Result<(), MsigdError> {
+        let file = StoreFile {
+            version: STORE_VERSION,
+            enabled: state.enabled,
+            monitors: state.monitors.clone(),
+        };
+        write_json(&self.path, &file)
+    }
+}
+
+/// Write changed restore settings to disk periodically in the background
+pub fn spawn_flush(store: Arc<RestoreStore>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            if let Err(e) = store.flush() {
+                eprintln!("Failed to save restore settings: {}", e);
+            }
+        }
+    });
+}
+
+/// Backend wrapper that remembers successful writes in a [`RestoreStore`]
+///
+/// Writes are recorded under the monitor's stable id, which is what restore
+/// looks up on reconnect, even when the caller passed an msigd index.
+pub struct RestoreRecorder {
+    inner: SharedBackend,
+    store: Arc<RestoreStore>,
+    ids: StableIdLookup,
+}
+
+impl RestoreRecorder {
+    pub fn new(inner: SharedBackend, store: Arc<RestoreStore>) -> Self {
+        Self {
+            inner,
+            store,
+            ids: StableIdLookup::default(),
+        }
+    }
+
+    /// Run `record` with the stable id of the monitor that was written
+    async fn remember(
+        &self,
+        monitor_id: &str,
+        record: impl FnOnce(&RestoreStore, &str) -> Result<(), MsigdError>,
+    ) {
+        if !self.store.enabled() {
+            return;
+        }
+        let Some(id) = self.ids.resolve(self.inner.as_ref(), monitor_id).await else {
+            eprintln!("Not remembering settings for unknown monitor {}", monitor_id);
+            return;
+        };
+        // A failed save must not turn a successful write into an error
+        if let Err(e) = record(&self.store, &id) {
+            eprintln!("Failed to remember settings for restore: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for RestoreRecorder {
+    async fn list(&self) -> Result<String, MsigdError> {
+        let output = self.inner.list().await?;
+        self.ids.remember(&output);
+        Ok(output)
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.inner.query(monitor_id).await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        let output = self.inner.set(monitor_id, setting, value).await?;
+        let pair = [(setting.to_string(), value.to_string())];
+        self.remember(monitor_id, |store, id| store.record(id, &pair))
+            .await;
+        Ok(output)
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        let output = self.inner.set_many(monitor_id, settings).await?;
+        self.remember(monitor_id, |store, id| store.record(id, settings))
+            .await;
+        Ok(output)
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        let output = self.inner.mystic(monitor_id, config).await?;
+        self.remember(monitor_id, |store, id| store.record_mystic(id, config))
+            .await;
+        Ok(output)
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        self.inner.invalidate(monitor_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msigd::backend;
+    use crate::msigd::identity::StableIds;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+    use crate::msigd::MonitorSettings;
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn test_replays_last_writes_after_reconnect() {
+        let dir = TempDir::new("restore");
+        let path = dir.join(RESTORE_FILE);
+        let store = Arc::new(RestoreStore::load(path.clone()));
+        let mock = Arc::new(MockBackend::new(vec![MockMonitor::new("SN1", "MAG274QRF-QD")]));
+        let ids: SharedBackend = Arc::new(StableIds::new(mock.clone()));
+        let recorder = RestoreRecorder::new(ids.clone(), store.clone());
+
+        // Nothing is remembered until enabled
+        recorder.set("SN1", "contrast", "20").await.unwrap();
+        store.set_enabled(true).unwrap();
+        // Writes by msigd index are remembered under the stable id
+        recorder.set("1", "brightness", "80").await.unwrap();
+        recorder.set("SN1", "input", "dp").await.unwrap();
+        recorder.set("SN1", "sharpness", "9").await.unwrap_err();
+        recorder.mystic("SN1", "1:static:0xFF0000").await.unwrap();
+        assert!(store.last_applied("1").is_none());
+        store.flush().unwrap();
+
+        // Power cycle: the monitor comes back with factory settings
+        mock.unplug("1");
+        mock.plug(MockMonitor::new("SN1", "MAG274QRF-QD"));
+        let monitor = backend::monitors(ids.as_ref()).await.unwrap().remove(0);
+        let reloaded = RestoreStore::load(path.clone());
+        let report = reloaded.restore(ids.as_ref(), &monitor).await.unwrap();
+
+        let fields: Vec<&str> = report.results.iter().map(|r| r.field.as_str()).collect();
+        assert_eq!(fields, ["brightness", "mysticLight"]);
+        assert!(report.results.iter().all(|r| r.success));
+        let restored = mock.monitor("1").unwrap();
+        assert_eq!(restored.settings.brightness, 80);
+        assert_eq!(restored.settings.contrast, MonitorSettings::default().contrast);
+        assert_eq!(restored.mystic.as_deref(), Some("1:static:0xFF0000"));
+        assert_eq!(reloaded.log().len(), 1);
+
+        reloaded.set_enabled(false).unwrap();
+        assert!(reloaded.restore(ids.as_ref(), &monitor).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_flush_writes_only_changed_settings() {
+        let dir = TempDir::new("restore-flush");
+        let path = dir.join(RESTORE_FILE);
+        let store = Arc::new(RestoreStore::load(path.clone()));
+        let mock = Arc::new(MockBackend::new(vec![MockMonitor::new("SN1", "MAG274QRF-QD")]));
+        let recorder = RestoreRecorder::new(Arc::new(StableIds::new(mock)), store.clone());
+        store.set_enabled(true).unwrap();
+
+        // Writes stay in memory until flushed
+        fs::remove_file(&path).unwrap();
+        recorder.set("SN1", "brightness", "80").await.unwrap();
+        assert!(!path.exists());
+        store.flush().unwrap();
+        assert!(path.exists());
+
+        // Rewriting the same value leaves nothing to flush
+        fs::remove_file(&path).unwrap();
+        recorder.set("SN1", "brightness", "80").await.unwrap();
+        store.flush().unwrap();
+        assert!(!path.exists());
+
+        recorder.set("SN1", "brightness", "60").await.unwrap();
+        store.flush().unwrap();
+        let reloaded = RestoreStore::load(path.clone());
+        assert_eq!(reloaded.last_applied("SN1").unwrap().settings.brightness, Some(60));
+    }
+}
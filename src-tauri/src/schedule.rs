@@ -0,0 +1,735 @@
+//! Time-of-day schedules
+//!
+//! Rules like "at 20:00 set brightness 30 and eye saver on" or "at 08:00
+//! apply profile Day" are stored per monitor and run by a background task,
+//! whether or not the window is visible. Numeric settings can ramp to their
+//! target over a while instead of jumping; like transitions, a ramp stops
+//! moving a setting once anything else writes it. Rules fire when their time passes
+//! while the app is running; missed times are not replayed on startup. Rules
+//! that came due together, e.g. during a suspend, fire in the order they were
+//! due, and a time repeated when clocks go back only fires the first time.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::MsigdError;
+use crate::msigd::backend::MonitorBackend;
+use crate::msigd::batch::SettingsBatch;
+use crate::msigd::registry::{self, SettingDef, SettingKind};
+use crate::msigd::{FieldResult, MsigdParser, PartialMonitorSettings};
+use crate::profiles::{write_json, ProfileStore};
+use crate::transition::Transitions;
+
+/// File name of the schedule store inside the app config dir
+pub const SCHEDULES_FILE: &str = "schedules.json";
+
+/// Event emitted with the `ScheduleRun` after a rule fired
+pub const SCHEDULE_APPLIED: &str = "schedule-applied";
+
+/// How often rules are checked and ramps advanced
+pub const TICK_INTERVAL: Duration = Duration::from_secs(5);
+
+const STORE_VERSION: u32 = 1;
+
+/// Source of local time, replaceable in tests
+pub trait Clock: Send + Sync {
+    /// Current time with the UTC offset in effect
+    fn now(&self) -> DateTime<FixedOffset>;
+    /// When a local wall-clock time happens, the first time if it repeats
+    fn resolve(&self, local: NaiveDateTime) -> Option<DateTime<FixedOffset>>;
+}
+
+/// The system clock in the local timezone
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().fixed_offset()
+    }
+
+    fn resolve(&self, local: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        // Times skipped when clocks go forward happen at the end of the gap
+        Local
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| Local.from_local_datetime(&(local + TimeDelta::hours(1))).earliest())
+            .map(|at| at.fixed_offset())
+    }
+}
+
+/// Local time of day a rule fires, written "HH:MM"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(NaiveTime);
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = MsigdError;
+
+    fn try_from(time: String) -> Result<Self, MsigdError> {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M").map(Self).map_err(|_| {
+            MsigdError::InvalidValue(format!("Invalid time {:?}, expected HH:MM", time))
+        })
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> String {
+        time.0.format("%H:%M").to_string()
+    }
+}
+
+/// What a rule does when it fires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduleAction {
+    /// Write settings, ramping numeric ones over the rule's ramp
+    Settings { settings: PartialMonitorSettings },
+    /// Apply a saved profile
+    Profile { name: String },
+}
+
+/// A daily rule for one monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRule {
+    pub name: String,
+    pub monitor_id: String,
+    pub time: TimeOfDay,
+    pub action: ScheduleAction,
+    /// Seconds over which numeric settings move to their target
+    #[serde(default)]
+    pub ramp_secs: u32,
+    pub enabled: bool,
+}
+
+impl ScheduleRule {
+    /// Latest time the rule came due at or before `now`
+    fn last_due(
+        &self,
+        clock: &dyn Clock,
+        now: DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let today = now.naive_local().date();
+        [Some(today), today.pred_opt()]
+            .into_iter()
+            .flatten()
+            .filter_map(|date| clock.resolve(date.and_time(self.time.0)))
+            .filter(|at| *at <= now)
+            .max()
+    }
+}
+
+/// Outcome of a rule firing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRun {
+    pub rule: String,
+    pub monitor_id: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Ramped fields are reported as started; their steps are logged
+    pub results: Vec<FieldResult>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    rules: Vec<ScheduleRule>,
+}
+
+/// Schedule rules kept in memory and mirrored to a JSON file
+pub struct ScheduleStore {
+    path: PathBuf,
+    rules: Mutex<Vec<ScheduleRule>>,
+}
+
+impl ScheduleStore {
+    /// Load rules from `path`, starting empty if missing or unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let rules = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoreFile>(&json).ok())
+            .map(|file| file.rules)
+            .unwrap_or_default();
+
+        Self {
+            path,
+            rules: Mutex::new(rules),
+        }
+    }
+
+    pub fn list(&self) -> Vec<ScheduleRule> {
+        self.rules.lock().unwrap().clone()
+    }
+
+    /// Insert a rule, replacing any existing one with the same name
+    pub fn save(&self, rule: ScheduleRule) -> Result<(), MsigdError> {
+        if rule.name.trim().is_empty() {
+            return Err(MsigdError::InvalidValue(
+                "Rule name must not be empty".to_string(),
+            ));
+        }
+        let mut rules = self.rules.lock().unwrap();
+        match rules.iter_mut().find(|r| r.name == rule.name) {
+            Some(existing) => *existing = rule,
+            None => rules.push(rule),
+        }
+        self.persist(&rules)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), MsigdError> {
+        let mut rules = self.rules.lock().unwrap();
+        let len = rules.len();
+        rules.retain(|r| r.name != name);
+        if rules.len() == len {
+            return Err(MsigdError::RuleNotFound(name.to_string()));
+        }
+        self.persist(&rules)
+    }
+
+    fn persist(&self, rules: &[ScheduleRule]) -> Result<(), MsigdError> {
+        let file = StoreFile {
+            version: STORE_VERSION,
+            rules: rules.to_vec(),
+        };
+        write_json(&self.path, &file)
+    }
+}
+
+/// A numeric setting moving towards a rule's target
+struct RampField {
+    def: &'static SettingDef,
+    from: u8,
+    to: u8,
+    sent: u8,
+    /// Claim on the setting, lost when anything else writes it
+    claim: u64,
+}
+
+impl RampField {
+    fn value_at(&self, progress: f64) -> u8 {
+        let value = f64::from(self.from) + (f64::from(self.to) - f64::from(self.from)) * progress;
+        value.round() as u8
+    }
+}
+
+struct Ramp {
+    rule: String,
+    monitor_id: String,
+    start: DateTime<FixedOffset>,
+    duration: TimeDelta,
+    fields: Vec<RampField>,
+}
+
+impl Ramp {
+    fn progress(&self, now: DateTime<FixedOffset>) -> f64 {
+        let elapsed = (now - self.start).num_milliseconds() as f64;
+        (elapsed / self.duration.num_milliseconds() as f64).clamp(0.0, 1.0)
+    }
+
+    fn release(&self, transitions: &Transitions) {
+        for field in &self.fields {
+            transitions.release(&self.monitor_id, field.def.flag, field.claim);
+        }
+    }
+}
+
+/// Fires due rules and steps running ramps on every tick
+pub struct Scheduler {
+    clock: Arc<dyn Clock>,
+    last_tick: Option<DateTime<FixedOffset>>,
+    ramps: Vec<Ramp>,
+}
+
+impl Scheduler {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            last_tick: None,
+            ramps: Vec::new(),
+        }
+    }
+
+    /// Run rules whose time passed since the last tick and advance ramps
+    ///
+    /// The first tick only records the time, so rules never fire for times
+    /// before the scheduler started.
+    pub async fn tick(
+        &mut self,
+        backend: &Transitions,
+        rules: &[ScheduleRule],
+        profiles: &ProfileStore,
+    ) -> Vec<ScheduleRun> {
+        let now = self.clock.now();
+        let mut runs = Vec::new();
+        if let Some(since) = self.last_tick.replace(now) {
+            let mut due: Vec<_> = rules
+                .iter()
+                .filter(|rule| rule.enabled)
+                .filter_map(|rule| {
+                    let at = rule.last_due(self.clock.as_ref(), now)?;
+                    (at > since).then_some((at, rule))
+                })
+                .collect();
+            due.sort_by_key(|(at, _)| *at);
+            for (_, rule) in due {
+                runs.push(self.fire(backend, rule, profiles, now).await);
+            }
+        }
+        self.advance(backend, now).await;
+        runs
+    }
+
+    async fn fire(
+        &mut self,
+        backend: &Transitions,
+        rule: &ScheduleRule,
+        profiles: &ProfileStore,
+        now: DateTime<FixedOffset>,
+    ) -> ScheduleRun {
+        let results = match &rule.action {
+            ScheduleAction::Profile { name } => {
+                self.ramps.retain(|ramp| {
+                    let keep = ramp.monitor_id != rule.monitor_id;
+                    if !keep {
+                        ramp.release(backend);
+                    }
+                    keep
+                });
+                match profiles.get(name) {
+                    Ok(profile) => profile.apply(backend, &rule.monitor_id).await,
+                    Err(e) => vec![FieldResult::failed("profile", &e)],
+                }
+            }
+            ScheduleAction::Settings { settings } => {
+                self.start(backend, rule, settings, now).await
+            }
+        };
+
+        ScheduleRun {
+            rule: rule.name.clone(),
+            monitor_id: rule.monitor_id.clone(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            results,
+        }
+    }
+
+    /// Write non-numeric settings now and start a ramp for numeric ones
+    async fn start(
+        &mut self,
+        backend: &Transitions,
+        rule: &ScheduleRule,
+        settings: &PartialMonitorSettings,
+        now: DateTime<FixedOffset>,
+    ) -> Vec<FieldResult> {
+        let mut targets = serde_json::to_value(settings).unwrap_or(Value::Null);
+
+        // A newer rule takes over settings an older ramp is still moving
+        for ramp in &mut self.ramps {
+            if ramp.monitor_id == rule.monitor_id {
+                ramp.fields.retain(|f| targets[f.def.field].is_null());
+            }
+        }
+        self.ramps.retain(|r| !r.fields.is_empty());
+
+        let mut fields = Vec::new();
+        if rule.ramp_secs > 0 {
+            let current = match backend.query(&rule.monitor_id).await {
+                Ok(output) => MsigdParser::parse_settings(&output)
+                    .ok()
+                    .and_then(|s| serde_json::to_value(s).ok()),
+                Err(_) => None,
+            };
+            for def in registry::SETTINGS {
+                let target = &targets[def.field];
+                // Out of range targets are left for the batch to reject
+                let rampable = matches!(def.kind, SettingKind::Numeric { .. })
+                    && def.msigd_value(target).is_ok();
+                let from = current.as_ref().and_then(|c| c[def.field].as_u64());
+                if let (true, Some(from), Some(to)) = (rampable, from, target.as_u64()) {
+                    fields.push(RampField {
+                        def,
+                        from: from as u8,
+                        to: to as u8,
+                        sent: from as u8,
+                        claim: backend.claim(&rule.monitor_id, def.flag),
+                    });
+                    targets[def.field] = Value::Null;
+                }
+            }
+        }
+
+        let immediate = serde_json::from_value(targets).unwrap_or_default();
+        let mut results = SettingsBatch::from_partial(&immediate)
+            .apply(backend, &rule.monitor_id)
+            .await;
+        results.extend(fields.iter().map(|f| FieldResult::ok(f.def.field)));
+        if !fields.is_empty() {
+            self.ramps.push(Ramp {
+                rule: rule.name.clone(),
+                monitor_id: rule.monitor_id.clone(),
+                start: now,
+                duration: TimeDelta::seconds(i64::from(rule.ramp_secs)),
+                fields,
+            });
+        }
+        results
+    }
+
+    /// Send the next step of every ramp whose values changed
+    ///
+    /// Settings written by anything else since the last step leave the ramp.
+    async fn advance(&mut self, backend: &Transitions, now: DateTime<FixedOffset>) {
+        for ramp in &mut self.ramps {
+            let monitor_id = &ramp.monitor_id;
+            ramp.fields
+                .retain(|field| backend.holds(monitor_id, field.def.flag, field.claim));
+            let progress = ramp.progress(now);
+            let steps: Vec<(String, String, u64)> = ramp
+                .fields
+                .iter_mut()
+                .filter_map(|field| {
+                    let value = field.value_at(progress);
+                    (value != field.sent).then(|| {
+                        field.sent = value;
+                        (field.def.flag.to_string(), value.to_string(), field.claim)
+                    })
+                })
+                .collect();
+            if steps.is_empty() {
+                continue;
+            }
+            if let Err(e) = backend.step(monitor_id, &steps).await {
+                eprintln!("Schedule {} failed to ramp {}: {}", ramp.rule, monitor_id, e);
+            }
+        }
+        self.ramps.retain(|ramp| {
+            let running = !ramp.fields.is_empty() && ramp.progress(now) < 1.0;
+            if !running {
+                ramp.release(backend);
+            }
+            running
+        });
+    }
+}
+
+/// Run schedule rules in the background for as long as the app runs
+pub fn spawn(app: AppHandle, backend: Arc<Transitions>, store: Arc<ScheduleStore>) {
+    tauri::async_runtime::spawn(async move {
+        let mut scheduler = Scheduler::new(Arc::new(SystemClock));
+        loop {
+            let profiles = app.state::<ProfileStore>();
+            let runs = scheduler
+                .tick(backend.as_ref(), &store.list(), &profiles)
+                .await;
+            for run in runs {
+                log_run(&run);
+                let _ = app.emit(SCHEDULE_APPLIED, run);
+            }
+            tokio::time::sleep(TICK_INTERVAL).await;
+        }
+    });
+}
+
+fn log_run(run: &ScheduleRun) {
+    for result in &run.results {
+        if let Some(e) = &result.error {
+            eprintln!(
+                "Schedule {} failed to set {} on {}: {}",
+                run.rule, result.field, run.monitor_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+
+    use super::*;
+    use crate::msigd::mock::MockBackend;
+    use crate::profiles::Profile;
+    use crate::test_support::TempDir;
+
+    /// Clock in a zone at UTC+2 that goes back to UTC+1 at 03:00 on the day
+    /// after the fixed test day, like central Europe on 2024-10-27
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl FakeClock {
+        fn at(time: &str) -> Arc<Self> {
+            let clock = Arc::new(Self(Mutex::new(Utc::now())));
+            clock.set(time);
+            clock
+        }
+
+        /// Set the local time, taking the first of a repeated time
+        fn set(&self, time: &str) {
+            let at = self.resolve(datetime(time)).unwrap();
+            *self.0.lock().unwrap() = at.to_utc();
+        }
+
+        fn advance(&self, secs: i64) {
+            *self.0.lock().unwrap() += TimeDelta::seconds(secs);
+        }
+
+        fn offset(at: DateTime<Utc>) -> FixedOffset {
+            let fall_back = NaiveDate::from_ymd_opt(2024, 10, 27)
+                .unwrap()
+                .and_hms_opt(1, 0, 0)
+                .unwrap()
+                .and_utc();
+            let hours = if at < fall_back { 2 } else { 1 };
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<FixedOffset> {
+            let at = *self.0.lock().unwrap();
+            at.with_timezone(&Self::offset(at))
+        }
+
+        fn resolve(&self, local: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+            [2, 1]
+                .into_iter()
+                .map(|hours| (local - TimeDelta::hours(hours)).and_utc())
+                .map(|at| at.with_timezone(&Self::offset(at)))
+                .filter(|at| at.naive_local() == local)
+                .min()
+        }
+    }
+
+    /// "HH:MM:SS" on a fixed day, or "+HH:MM:SS" on the day after
+    fn datetime(time: &str) -> NaiveDateTime {
+        let (day, time) = match time.strip_prefix('+') {
+            Some(time) => (27, time),
+            None => (26, time),
+        };
+        NaiveDate::from_ymd_opt(2024, 10, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M:%S").unwrap())
+    }
+
+    fn rule(name: &str, time: &str, action: ScheduleAction, ramp_secs: u32) -> ScheduleRule {
+        ScheduleRule {
+            name: name.to_string(),
+            monitor_id: "1".to_string(),
+            time: TimeOfDay::try_from(time.to_string()).unwrap(),
+            action,
+            ramp_secs,
+            enabled: true,
+        }
+    }
+
+    fn transitions() -> (Arc<MockBackend>, Transitions) {
+        let mock = Arc::new(MockBackend::default());
+        (mock.clone(), Transitions::new(mock))
+    }
+
+    #[tokio::test]
+    async fn test_fires_rules_and_ramps_numeric_settings() {
+        let (backend, transitions) = transitions();
+        let dir = TempDir::new("schedule-run");
+        let profiles = ProfileStore::load(dir.join("profiles.json"));
+        profiles
+            .save(Profile {
+                name: "Day".to_string(),
+                settings: PartialMonitorSettings {
+                    brightness: Some(90),
+                    eye_saver: Some(false),
+                    ..Default::default()
+                },
+                mystic_light: None,
+            })
+            .unwrap();
+        backend.set("1", "brightness", "70").await.unwrap();
+        let evening = ScheduleAction::Settings {
+            settings: PartialMonitorSettings {
+                brightness: Some(30),
+                eye_saver: Some(true),
+                ..Default::default()
+            },
+        };
+        let rules = [
+            rule("Evening", "20:00", evening, 40),
+            rule("Morning", "08:00", ScheduleAction::Profile { name: "Day".into() }, 0),
+        ];
+        let clock = FakeClock::at("19:59:55");
+        let mut scheduler = Scheduler::new(clock.clone());
+        let brightness = || backend.monitor("1").unwrap().settings.brightness;
+
+        assert!(scheduler.tick(&transitions, &rules, &profiles).await.is_empty());
+        clock.set("20:00:00");
+        let runs = scheduler.tick(&transitions, &rules, &profiles).await;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].rule, "Evening");
+        assert!(runs[0].results.iter().all(|r| r.success));
+        assert!(backend.monitor("1").unwrap().settings.eye_saver);
+        assert_eq!(brightness(), 70);
+
+        clock.set("20:00:20");
+        scheduler.tick(&transitions, &rules, &profiles).await;
+        assert_eq!(brightness(), 50);
+        clock.set("20:05:00");
+        scheduler.tick(&transitions, &rules, &profiles).await;
+        assert_eq!(brightness(), 30);
+
+        // Crossing midnight and firing a profile
+        clock.set("+08:00:01");
+        let runs = scheduler.tick(&transitions, &rules, &profiles).await;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].rule, "Morning");
+        assert_eq!(brightness(), 90);
+        assert!(!backend.monitor("1").unwrap().settings.eye_saver);
+    }
+
+    #[tokio::test]
+    async fn test_rules_due_together_fire_in_time_order() {
+        let (backend, transitions) = transitions();
+        let dir = TempDir::new("schedule-suspend");
+        let profiles = ProfileStore::load(dir.join("profiles.json"));
+        let set = |brightness, eye_saver| ScheduleAction::Settings {
+            settings: PartialMonitorSettings {
+                brightness: Some(brightness),
+                eye_saver: Some(eye_saver),
+                ..Default::default()
+            },
+        };
+        let rules = [
+            rule("Morning", "08:00", set(90, false), 0),
+            rule("Evening", "20:00", set(30, true), 0),
+        ];
+        let clock = FakeClock::at("19:59:55");
+        let mut scheduler = Scheduler::new(clock.clone());
+
+        scheduler.tick(&transitions, &rules, &profiles).await;
+        // Suspended overnight
+        clock.set("+08:00:01");
+        let runs = scheduler.tick(&transitions, &rules, &profiles).await;
+
+        let names: Vec<&str> = runs.iter().map(|r| r.rule.as_str()).collect();
+        assert_eq!(names, ["Evening", "Morning"]);
+        let settings = backend.monitor("1").unwrap().settings;
+        assert_eq!(settings.brightness, 90);
+        assert!(!settings.eye_saver);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_hour_fires_once() {
+        let (_, transitions) = transitions();
+        let dir = TempDir::new("schedule-dst");
+        let profiles = ProfileStore::load(dir.join("profiles.json"));
+        let dim = ScheduleAction::Settings {
+            settings: PartialMonitorSettings {
+                brightness: Some(20),
+                ..Default::default()
+            },
+        };
+        let rules = [rule("Night", "02:30", dim, 0)];
+        let clock = FakeClock::at("+02:29:55");
+        let mut scheduler = Scheduler::new(clock.clone());
+
+        scheduler.tick(&transitions, &rules, &profiles).await;
+        clock.set("+02:30:00");
+        assert_eq!(scheduler.tick(&transitions, &rules, &profiles).await.len(), 1);
+        // 03:00 becomes 02:00 again, then 02:30 comes round a second time
+        let mut runs = Vec::new();
+        for _ in 0..(3600 / 5 + 1) {
+            clock.advance(5);
+            runs.extend(scheduler.tick(&transitions, &rules, &profiles).await);
+        }
+        assert_eq!(clock.now().naive_local(), datetime("+02:30:05"));
+        assert!(runs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_newer_rule_takes_over_ramp() {
+        let (backend, transitions) = transitions();
+        let dir = TempDir::new("schedule-takeover");
+        let profiles = ProfileStore::load(dir.join("profiles.json"));
+        backend.set("1", "brightness", "100").await.unwrap();
+        let dim = |brightness| ScheduleAction::Settings {
+            settings: PartialMonitorSettings {
+                brightness: Some(brightness),
+                ..Default::default()
+            },
+        };
+        let rules = [
+            rule("Slow", "20:00", dim(0), 100),
+            rule("Now", "20:01", dim(60), 0),
+        ];
+        let clock = FakeClock::at("19:59:00");
+        let mut scheduler = Scheduler::new(clock.clone());
+
+        for time in ["19:59:00", "20:00:00", "20:00:50", "20:01:00", "20:01:30"] {
+            clock.set(time);
+            scheduler.tick(&transitions, &rules, &profiles).await;
+        }
+
+        assert_eq!(backend.monitor("1").unwrap().settings.brightness, 60);
+        let writes: Vec<String> = backend.writes().into_iter().map(|w| w.2).collect();
+        assert_eq!(writes, ["100", "50", "60"]);
+    }
+
+    #[tokio::test]
+    async fn test_manual_write_stops_ramp() {
+        let (backend, transitions) = transitions();
+        let dir = TempDir::new("schedule-manual");
+        let profiles = ProfileStore::load(dir.join("profiles.json"));
+        backend.set("1", "brightness", "100").await.unwrap();
+        backend.set("1", "contrast", "0").await.unwrap();
+        let dim = ScheduleAction::Settings {
+            settings: PartialMonitorSettings {
+                brightness: Some(0),
+                contrast: Some(100),
+                ..Default::default()
+            },
+        };
+        let rules = [rule("Slow", "20:00", dim, 100)];
+        let clock = FakeClock::at("19:59:00");
+        let mut scheduler = Scheduler::new(clock.clone());
+
+        for time in ["19:59:00", "20:00:00", "20:00:50"] {
+            clock.set(time);
+            scheduler.tick(&transitions, &rules, &profiles).await;
+        }
+        transitions.set("1", "brightness", "80").await.unwrap();
+        clock.set("20:01:40");
+        scheduler.tick(&transitions, &rules, &profiles).await;
+
+        let settings = backend.monitor("1").unwrap().settings;
+        assert_eq!(settings.brightness, 80);
+        assert_eq!(settings.contrast, 100);
+    }
+
+    #[test]
+    fn test_store_persists_rules() {
+        let dir = TempDir::new("schedules");
+        let path = dir.join(SCHEDULES_FILE);
+        let store = ScheduleStore::load(path.clone());
+        let action = ScheduleAction::Profile {
+            name: "Day".to_string(),
+        };
+        store.save(rule("Morning", "08:00", action, 0)).unwrap();
+
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"time\": \"08:00\""));
+        let reloaded = ScheduleStore::load(path.clone());
+        assert_eq!(reloaded.list()[0].time, store.list()[0].time);
+        assert!(matches!(
+            reloaded.delete("Evening"),
+            Err(MsigdError::RuleNotFound(_))
+        ));
+        assert!(TimeOfDay::try_from("25:00".to_string()).is_err());
+    }
+}
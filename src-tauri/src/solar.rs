@@ -0,0 +1,155 @@
+//! Offline solar position, sunrise and sunset
+//!
+//! Implements the NOAA solar calculator equations, which are accurate to
+//! about a minute for sunrise and sunset between latitudes ±72°. No network
+//! access or timezone data is needed; all times are UTC.
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Timelike, Utc};
+
+/// Zenith angle of the sun's center at sunrise and sunset, including
+/// atmospheric refraction and the solar disc radius
+const SUNRISE_ZENITH: f64 = 90.833;
+
+/// Sunrise and sunset on one day
+///
+/// Both are `None` during polar day or polar night; `polar_day` tells which.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunTimes {
+    pub sunrise: Option<DateTime<Utc>>,
+    pub sunset: Option<DateTime<Utc>>,
+    pub polar_day: bool,
+}
+
+/// Sun declination and equation of time at an instant
+struct SolarCoordinates {
+    /// Declination in radians
+    declination: f64,
+    /// Equation of time in minutes
+    equation_of_time: f64,
+}
+
+fn solar_coordinates(at: DateTime<Utc>) -> SolarCoordinates {
+    let julian_day = at.timestamp() as f64 / 86400.0 + 2440587.5;
+    let t = (julian_day - 2451545.0) / 36525.0;
+
+    let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+    let mean_anomaly = (357.52911 + t * (35999.05029 - 0.0001537 * t)).to_radians();
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+
+    let center = mean_anomaly.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + (2.0 * mean_anomaly).sin() * (0.019993 - 0.000101 * t)
+        + (3.0 * mean_anomaly).sin() * 0.000289;
+    let omega = (125.04 - 1934.136 * t).to_radians();
+    let apparent_longitude =
+        (mean_longitude + center - 0.00569 - 0.00478 * omega.sin()).to_radians();
+
+    let mean_obliquity =
+        23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+    let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l0 = mean_longitude.to_radians();
+    let equation_of_time = 4.0
+        * (y * (2.0 * l0).sin() - 2.0 * eccentricity * mean_anomaly.sin()
+            + 4.0 * eccentricity * y * mean_anomaly.sin() * (2.0 * l0).cos()
+            - 0.5 * y * y * (4.0 * l0).sin()
+            - 1.25 * eccentricity * eccentricity * (2.0 * mean_anomaly).sin())
+        .to_degrees();
+
+    SolarCoordinates {
+        declination,
+        equation_of_time,
+    }
+}
+
+/// Elevation of the sun above the horizon in degrees, without refraction
+///
+/// `longitude` is positive east of Greenwich.
+pub fn solar_elevation(at: DateTime<Utc>, latitude: f64, longitude: f64) -> f64 {
+    let sun = solar_coordinates(at);
+    let minutes = at.num_seconds_from_midnight() as f64 / 60.0;
+    let true_solar_time = (minutes + sun.equation_of_time + 4.0 * longitude).rem_euclid(1440.0);
+    let hour_angle = (true_solar_time / 4.0 - 180.0).to_radians();
+
+    let latitude = latitude.to_radians();
+    let cos_zenith = latitude.sin() * sun.declination.sin()
+        + latitude.cos() * sun.declination.cos() * hour_angle.cos();
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// Sunrise and sunset on `date` at a location
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> SunTimes {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    // Evaluate the sun's position near local solar noon
+    let noon = midnight + TimeDelta::minutes((720.0 - 4.0 * longitude) as i64);
+    let sun = solar_coordinates(noon);
+
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = SUNRISE_ZENITH.to_radians().cos()
+        / (latitude.cos() * sun.declination.cos())
+        - latitude.tan() * sun.declination.tan();
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return SunTimes {
+            sunrise: None,
+            sunset: None,
+            polar_day: cos_hour_angle < -1.0,
+        };
+    }
+
+    let solar_noon = 720.0 - 4.0 * longitude - sun.equation_of_time;
+    let half_day = 4.0 * cos_hour_angle.acos().to_degrees();
+    let at = |minutes: f64| midnight + TimeDelta::seconds((minutes * 60.0).round() as i64);
+    SunTimes {
+        sunrise: Some(at(solar_noon - half_day)),
+        sunset: Some(at(solar_noon + half_day)),
+        polar_day: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date: &str, time: &str) -> DateTime<Utc> {
+        format!("{}T{}Z", date, time).parse().unwrap()
+    }
+
+    fn assert_near(actual: Option<DateTime<Utc>>, expected: DateTime<Utc>) {
+        let diff = (actual.unwrap() - expected).num_seconds().abs();
+        assert!(diff <= 120, "{:?} is not near {}", actual, expected);
+    }
+
+    #[test]
+    fn test_sun_times_match_published_tables() {
+        let date = |d: &str| d.parse::<NaiveDate>().unwrap();
+
+        // London, summer solstice: 04:43 and 21:21 BST
+        let london = sun_times(date("2024-06-21"), 51.5074, -0.1278);
+        assert_near(london.sunrise, utc("2024-06-21", "03:43:00"));
+        assert_near(london.sunset, utc("2024-06-21", "20:21:00"));
+
+        // Sydney, winter: 07:00 and 16:54 AEST
+        let sydney = sun_times(date("2024-06-21"), -33.8688, 151.2093);
+        assert_near(sydney.sunrise, utc("2024-06-20", "21:00:00"));
+        assert_near(sydney.sunset, utc("2024-06-21", "06:54:00"));
+
+        let tromso = |d| sun_times(date(d), 69.6492, 18.9553);
+        assert_eq!(tromso("2024-06-21").sunset, None);
+        assert!(tromso("2024-06-21").polar_day);
+        assert_eq!(tromso("2024-12-21").sunrise, None);
+        assert!(!tromso("2024-12-21").polar_day);
+    }
+
+    #[test]
+    fn test_solar_elevation() {
+        // Overhead at the equator around the equinox, below the horizon at night
+        assert!(solar_elevation(utc("2024-03-20", "12:07:00"), 0.0, 0.0) > 88.0);
+        assert!(solar_elevation(utc("2024-03-20", "00:00:00"), 0.0, 0.0) < -80.0);
+
+        // Near the horizon at sunrise
+        let berlin = sun_times("2024-09-01".parse().unwrap(), 52.52, 13.405);
+        let elevation = solar_elevation(berlin.sunrise.unwrap(), 52.52, 13.405);
+        assert!((-1.5..0.0).contains(&elevation), "{}", elevation);
+    }
+}
@@ -0,0 +1,81 @@
+//! Helpers shared by unit tests
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ambient::IIO_DEVICES;
+
+/// Empty scratch directory, removed again when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create a directory unique to this test process and `name`
+    pub fn new(name: &str) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("msigd-gui-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+
+    /// Write `contents` to `path` inside the directory, creating parents
+    pub fn write(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// Remove `path` inside the directory along with everything below it
+    pub fn remove(&self, path: impl AsRef<Path>) {
+        fs::remove_dir_all(self.0.join(path)).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Fake `/sys` with hidraw nodes and IIO devices
+pub struct FakeSysfs(TempDir);
+
+impl FakeSysfs {
+    pub fn new(name: &str) -> Self {
+        let dir = TempDir::new(name);
+        fs::create_dir_all(dir.join("class/hidraw")).unwrap();
+        fs::create_dir_all(dir.join(IIO_DEVICES)).unwrap();
+        Self(dir)
+    }
+
+    pub fn root(&self) -> &Path {
+        self.0.path()
+    }
+
+    /// Add a hidraw node for a HID device with `HID_ID` `hid_id`
+    pub fn add_hidraw(&self, node: &str, hid_id: &str) {
+        let uevent = format!("DRIVER=hid-generic\nHID_ID={}\n", hid_id);
+        self.0.write(
+            Path::new("class/hidraw").join(node).join("device/uevent"),
+            uevent,
+        );
+    }
+
+    pub fn remove_hidraw(&self, node: &str) {
+        self.0.remove(Path::new("class/hidraw").join(node));
+    }
+
+    /// Write an attribute of an IIO device
+    pub fn write_iio(&self, device: &str, file: &str, value: &str) {
+        let path = Path::new(IIO_DEVICES).join(device).join(file);
+        self.0.write(path, format!("{}\n", value));
+    }
+}
@@ -0,0 +1,480 @@
+//! Smooth transitions of numeric settings
+//!
+//! A transition steps a numeric or RGB setting from its current value to a
+//! target over a duration, shaped by an easing curve. Any other write to the
+//! same setting on the same monitor cancels it, as does a newer transition.
+//! [`Transitions`] is a backend wrapper so every write path (commands,
+//! profiles, schedules) cancels transitions without knowing about them.
+//! Ramps stepped elsewhere, like schedule ramps, take part through
+//! [`Transitions::claim`] and [`Transitions::step`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::error::MsigdError;
+use crate::msigd::backend::{MonitorBackend, SharedBackend};
+use crate::msigd::registry::{self, SettingDef, SettingKind};
+use crate::msigd::MsigdParser;
+
+/// Event emitted with a `TransitionProgress` after every step
+pub const TRANSITION_PROGRESS: &str = "transition-progress";
+
+/// Time between transition steps
+pub const STEP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest accepted transition
+const MAX_DURATION: Duration = Duration::from_secs(600);
+
+/// Shape of a transition over time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Eased progress for linear progress `t` in `0.0..=1.0`
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut if t < 0.5 => 2.0 * t * t,
+            Self::EaseInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+        }
+    }
+}
+
+/// Progress of a running transition
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionProgress {
+    pub monitor_id: String,
+    /// `MonitorSettings` field name
+    pub setting: String,
+    /// Value just written, in `MonitorSettings` form
+    pub value: Value,
+    /// Time-based progress from 0 to 1
+    pub progress: f64,
+}
+
+/// How a transition ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionOutcome {
+    Completed,
+    /// A newer write to the same setting took over
+    Cancelled,
+}
+
+/// A validated transition request
+struct Plan {
+    def: &'static SettingDef,
+    from: Vec<u8>,
+    to: Vec<u8>,
+}
+
+impl Plan {
+    /// msigd value at eased progress `p`
+    fn value_at(&self, p: f64) -> String {
+        self.from
+            .iter()
+            .zip(&self.to)
+            .map(|(from, to)| {
+                let value = f64::from(*from) + (f64::from(*to) - f64::from(*from)) * p;
+                (value.round() as u8).to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Channels of a numeric or RGB value in msigd syntax
+fn channels(value: &str) -> Vec<u8> {
+    value.split(',').filter_map(|c| c.trim().parse().ok()).collect()
+}
+
+/// Backend wrapper running transitions and cancelling them on other writes
+pub struct Transitions {
+    inner: SharedBackend,
+    step: Duration,
+    next_id: AtomicU64,
+    /// Id of the running transition per (monitor, msigd flag)
+    active: Mutex<HashMap<(String, String), u64>>,
+    /// Held while a step or a cancelling write is sent, so a cancelled
+    /// transition can never overwrite the write that cancelled it
+    write_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Transitions {
+    pub fn new(inner: SharedBackend) -> Self {
+        Self {
+            inner,
+            step: STEP_INTERVAL,
+            next_id: AtomicU64::new(0),
+            active: Mutex::new(HashMap::new()),
+            write_locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Override the time between steps
+    #[cfg(test)]
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    fn write_lock(&self, monitor_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.write_locks
+            .lock()
+            .unwrap()
+            .entry(monitor_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Start a ramp on `flag` stepped by the caller, cancelling any
+    /// transition running on it
+    ///
+    /// The returned id stays valid until another write to the flag.
+    pub fn claim(&self, monitor_id: &str, flag: &str) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.active
+            .lock()
+            .unwrap()
+            .insert((monitor_id.to_string(), flag.to_string()), id);
+        id
+    }
+
+    /// Whether the ramp claimed as `id` on `flag` was not cancelled yet
+    pub fn holds(&self, monitor_id: &str, flag: &str, id: u64) -> bool {
+        self.active
+            .lock()
+            .unwrap()
+            .get(&(monitor_id.to_string(), flag.to_string()))
+            == Some(&id)
+    }
+
+    /// End the ramp claimed as `id` on `flag` if it is still running
+    pub fn release(&self, monitor_id: &str, flag: &str, id: u64) {
+        let key = (monitor_id.to_string(), flag.to_string());
+        let mut active = self.active.lock().unwrap();
+        if active.get(&key) == Some(&id) {
+            active.remove(&key);
+        }
+    }
+
+    /// Write the next values of claimed ramps as `(flag, value, id)`
+    ///
+    /// Steps whose claim was cancelled are skipped, so a ramp never
+    /// overwrites the write that cancelled it.
+    pub async fn step(
+        &self,
+        monitor_id: &str,
+        steps: &[(String, String, u64)],
+    ) -> Result<(), MsigdError> {
+        let lock = self.write_lock(monitor_id);
+        let _write = lock.lock().await;
+        let writes: Vec<(String, String)> = steps
+            .iter()
+            .filter(|(flag, _, id)| self.holds(monitor_id, flag, *id))
+            .map(|(flag, value, _)| (flag.clone(), value.clone()))
+            .collect();
+        if !writes.is_empty() {
+            self.inner.set_many(monitor_id, &writes).await?;
+        }
+        Ok(())
+    }
+
+    /// Cancel transitions on `flags`, returning whether any were running
+    fn cancel<'a>(&self, monitor_id: &str, flags: impl IntoIterator<Item = &'a str>) -> bool {
+        let mut active = self.active.lock().unwrap();
+        let mut cancelled = false;
+        for flag in flags {
+            cancelled |= active
+                .remove(&(monitor_id.to_string(), flag.to_string()))
+                .is_some();
+        }
+        cancelled
+    }
+
+    async fn plan(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        target: &Value,
+    ) -> Result<Plan, MsigdError> {
+        let def = registry::lookup(setting)
+            .ok_or_else(|| MsigdError::InvalidValue(format!("Unknown setting: {}", setting)))?;
+        if !matches!(def.kind, SettingKind::Numeric { .. } | SettingKind::Rgb { .. }) {
+            return Err(MsigdError::InvalidValue(format!(
+                "{} cannot be transitioned",
+                def.label
+            ))
+            .with_setting(def.flag));
+        }
+        let to = channels(&def.msigd_value(target).map_err(|e| e.with_setting(def.flag))?);
+
+        let queried = MsigdParser::parse_query(&self.inner.query(monitor_id).await?)?;
+        // Unreported settings hold defaults, not a value to start from
+        if !queried.reported.iter().any(|f| f == def.field) {
+            return Err(
+                MsigdError::UnsupportedSetting(def.label.to_string()).with_setting(def.flag)
+            );
+        }
+        let current = serde_json::to_value(&queried.settings)
+            .map_err(|e| MsigdError::ParseError(e.to_string()))?;
+        let from = channels(&def.msigd_value(&current[def.field])?);
+        Ok(Plan { def, from, to })
+    }
+
+    /// Step `setting` to `target` over `duration`, reporting every step
+    ///
+    /// Resolves once the target is reached or the transition is cancelled.
+    pub async fn run(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        target: &Value,
+        duration: Duration,
+        easing: Easing,
+        mut on_progress: impl FnMut(TransitionProgress) + Send,
+    ) -> Result<TransitionOutcome, MsigdError> {
+        if duration > MAX_DURATION {
+            return Err(MsigdError::InvalidValue(format!(
+                "Transitions can take at most {} seconds",
+                MAX_DURATION.as_secs()
+            )));
+        }
+        let plan = self
+            .plan(monitor_id, setting, target)
+            .await
+            .map_err(|e| e.with_monitor(monitor_id))?;
+        let flag = plan.def.flag;
+        let id = self.claim(monitor_id, flag);
+
+        let start = Instant::now();
+        let mut sent = plan.value_at(0.0);
+        loop {
+            let progress = if duration.is_zero() {
+                1.0
+            } else {
+                (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.0)
+            };
+            let value = plan.value_at(easing.apply(progress));
+            let changed = value != sent;
+
+            let lock = self.write_lock(monitor_id);
+            let write = lock.lock().await;
+            if !self.holds(monitor_id, flag, id) {
+                return Ok(TransitionOutcome::Cancelled);
+            }
+            if changed {
+                if let Err(e) = self.inner.set(monitor_id, flag, &value).await {
+                    self.release(monitor_id, flag, id);
+                    return Err(e.with_setting(flag).with_monitor(monitor_id));
+                }
+            }
+            if changed || progress >= 1.0 {
+                on_progress(TransitionProgress {
+                    monitor_id: monitor_id.to_string(),
+                    setting: plan.def.field.to_string(),
+                    value: plan.def.parse_value(&value).unwrap_or(Value::Null),
+                    progress,
+                });
+                sent = value;
+            }
+
+            if progress >= 1.0 {
+                self.release(monitor_id, flag, id);
+                return Ok(TransitionOutcome::Completed);
+            }
+            drop(write);
+            tokio::time::sleep(self.step).await;
+        }
+    }
+}
+
+#[async_trait]
+impl MonitorBackend for Transitions {
+    async fn list(&self) -> Result<String, MsigdError> {
+        self.inner.list().await
+    }
+
+    async fn query(&self, monitor_id: &str) -> Result<String, MsigdError> {
+        self.inner.query(monitor_id).await
+    }
+
+    async fn set(
+        &self,
+        monitor_id: &str,
+        setting: &str,
+        value: &str,
+    ) -> Result<String, MsigdError> {
+        if !self.cancel(monitor_id, [setting]) {
+            return self.inner.set(monitor_id, setting, value).await;
+        }
+        let lock = self.write_lock(monitor_id);
+        let _write = lock.lock().await;
+        self.inner.set(monitor_id, setting, value).await
+    }
+
+    async fn set_many(
+        &self,
+        monitor_id: &str,
+        settings: &[(String, String)],
+    ) -> Result<String, MsigdError> {
+        let flags = settings.iter().map(|(setting, _)| setting.as_str());
+        if !self.cancel(monitor_id, flags) {
+            return self.inner.set_many(monitor_id, settings).await;
+        }
+        let lock = self.write_lock(monitor_id);
+        let _write = lock.lock().await;
+        self.inner.set_many(monitor_id, settings).await
+    }
+
+    async fn mystic(&self, monitor_id: &str, config: &str) -> Result<String, MsigdError> {
+        self.inner.mystic(monitor_id, config).await
+    }
+
+    async fn check_available(&self) -> Result<bool, MsigdError> {
+        self.inner.check_available().await
+    }
+
+    async fn invalidate(&self, monitor_id: &str) {
+        self.inner.invalidate(monitor_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::msigd::mock::{MockBackend, MockMonitor};
+
+    fn transitions() -> (Arc<MockBackend>, Arc<Transitions>) {
+        let mock = Arc::new(MockBackend::default());
+        let transitions = Transitions::new(mock.clone()).with_step(Duration::from_millis(5));
+        (mock, Arc::new(transitions))
+    }
+
+    #[test]
+    fn test_easing_curves() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_steps_to_target_with_progress() {
+        let (mock, transitions) = transitions();
+        mock.set("1", "brightness", "100").await.unwrap();
+        let mut steps = Vec::new();
+
+        let outcome = transitions
+            .run(
+                "1",
+                "brightness",
+                &json!(20),
+                Duration::from_millis(60),
+                Easing::EaseInOut,
+                |p| steps.push(p),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, TransitionOutcome::Completed);
+        assert_eq!(mock.monitor("1").unwrap().settings.brightness, 20);
+        assert!(steps.len() > 1);
+        assert_eq!(steps.last().unwrap().value, json!(20));
+        assert_eq!(steps.last().unwrap().progress, 1.0);
+        let values: Vec<u64> = steps.iter().map(|p| p.value.as_u64().unwrap()).collect();
+        assert!(values.windows(2).all(|w| w[0] >= w[1]), "{:?}", values);
+    }
+
+    #[tokio::test]
+    async fn test_rgb_channels_and_validation() {
+        let (mock, transitions) = transitions();
+        let target = json!({ "r": 10, "g": 20, "b": 30 });
+
+        transitions
+            .run("1", "colorRgb", &target, Duration::ZERO, Easing::Linear, |_| {})
+            .await
+            .unwrap();
+        let rgb = mock.monitor("1").unwrap().settings.color_rgb;
+        assert_eq!((rgb.r, rgb.g, rgb.b), (10, 20, 30));
+
+        let run = |setting: &'static str, target: Value| {
+            let transitions = transitions.clone();
+            async move {
+                transitions
+                    .run("1", setting, &target, Duration::ZERO, Easing::Linear, |_| {})
+                    .await
+                    .unwrap_err()
+                    .code()
+            }
+        };
+        assert_eq!(run("eye_saver", json!(true)).await, "invalid_value");
+        assert_eq!(run("brightness", json!(101)).await, "invalid_value");
+    }
+
+    #[tokio::test]
+    async fn test_unreported_setting_fails() {
+        let mock = Arc::new(MockBackend::new(vec![
+            MockMonitor::new("SN1", "MAG274QRF-QD").without(&["sharpness"]),
+        ]));
+        let transitions = Transitions::new(mock.clone()).with_step(Duration::from_millis(5));
+
+        let err = transitions
+            .run("1", "sharpness", &json!(4), Duration::ZERO, Easing::Linear, |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "unsupported_setting");
+        assert_eq!(err.setting(), Some("sharpness"));
+        assert!(mock.writes().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_newer_write_cancels_transition() {
+        let (mock, transitions) = transitions();
+        mock.set("1", "brightness", "100").await.unwrap();
+
+        let running = {
+            let transitions = transitions.clone();
+            tokio::spawn(async move {
+                transitions
+                    .run(
+                        "1",
+                        "brightness",
+                        &json!(0),
+                        Duration::from_secs(5),
+                        Easing::Linear,
+                        |_| {},
+                    )
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        transitions.set("1", "contrast", "10").await.unwrap();
+        transitions.set("1", "brightness", "55").await.unwrap();
+
+        assert_eq!(running.await.unwrap().unwrap(), TransitionOutcome::Cancelled);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(mock.monitor("1").unwrap().settings.brightness, 55);
+    }
+}
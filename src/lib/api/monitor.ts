@@ -1,9 +1,73 @@
 // Tauri API bindings for monitor commands
 
-import type { Monitor, MonitorSettings, MysticLightMode } from "../types";
+import type {
+  AmbientConfig,
+  AppRule,
+  AppSwitch,
+  CircadianConfig,
+  CommandErrorBody,
+  DiagnosticReport,
+  Easing,
+  ErrorCode,
+  FieldResult,
+  ImportResult,
+  LightSensor,
+  Monitor,
+  MonitorCapabilities,
+  MonitorSettings,
+  MysticLightConfig,
+  MysticLightModeInfo,
+  PartialMonitorSettings,
+  Profile,
+  RestoreReport,
+  ScheduleRule,
+  ScheduleRun,
+  SettingDef,
+  SettingField,
+  SettingValue,
+  SunTimes,
+  TransitionOutcome,
+  TransitionProgress,
+} from "../types";
+
+/**
+ * Error thrown by command wrappers; String(e) yields the message
+ */
+export class CommandError extends Error {
+  code: ErrorCode;
+  monitorId?: string;
+  setting?: string;
+  stderr?: string;
+  exitStatus?: number;
+
+  constructor(body: CommandErrorBody) {
+    super(body.message);
+    this.name = "CommandError";
+    this.code = body.code;
+    this.monitorId = body.monitorId;
+    this.setting = body.setting;
+    this.stderr = body.stderr;
+    this.exitStatus = body.exitStatus;
+  }
+
+  toString(): string {
+    return this.message;
+  }
+}
 
 // Use global Tauri API if available, fallback to import
 async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  try {
+    return await rawInvoke<T>(cmd, args);
+  } catch (e) {
+    if (e && typeof e === "object" && "code" in e && "message" in e) {
+      throw new CommandError(e as CommandErrorBody);
+    }
+    throw e;
+  }
+}
+
+async function rawInvoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
   // Try global API first (set by withGlobalTauri)
   const win = window as any;
   if (win.__TAURI__?.core?.invoke) {
@@ -17,6 +81,72 @@ async function invoke<T>(cmd: string, args?: Record<string, unknown>): Promise<T
   return tauriInvoke(cmd, args);
 }
 
+async function getListen() {
+  const win = window as any;
+  return win.__TAURI__?.event?.listen ?? (await import("@tauri-apps/api/event")).listen;
+}
+
+/**
+ * Subscribe to monitor-restored events sent after settings were re-applied
+ */
+export async function onMonitorRestored(
+  callback: (report: RestoreReport) => void
+): Promise<() => void> {
+  const listen = await getListen();
+  return listen("monitor-restored", (e: { payload: RestoreReport }) => callback(e.payload));
+}
+
+/**
+ * Subscribe to schedule rules firing
+ * Returns a function that removes the listener
+ */
+export async function onScheduleApplied(
+  callback: (run: ScheduleRun) => void
+): Promise<() => void> {
+  const listen = await getListen();
+  return listen("schedule-applied", (e: { payload: ScheduleRun }) => callback(e.payload));
+}
+
+/**
+ * Subscribe to monitors switching profile as applications start and exit
+ * Returns a function that removes the listener
+ */
+export async function onAppProfileSwitched(
+  callback: (change: AppSwitch) => void
+): Promise<() => void> {
+  const listen = await getListen();
+  return listen("app-profile-switched", (e: { payload: AppSwitch }) => callback(e.payload));
+}
+
+/**
+ * Subscribe to progress of running setting transitions
+ * Returns a function that removes the listener
+ */
+export async function onTransitionProgress(
+  callback: (progress: TransitionProgress) => void
+): Promise<() => void> {
+  const listen = await getListen();
+  return listen("transition-progress", (e: { payload: TransitionProgress }) =>
+    callback(e.payload)
+  );
+}
+
+/**
+ * Subscribe to monitor-added/monitor-removed hotplug events
+ * Returns a function that removes the listeners
+ */
+export async function onMonitorHotplug(
+  callback: (event: "monitor-added" | "monitor-removed", monitor: Monitor) => void
+): Promise<() => void> {
+  const listen = await getListen();
+  const unlisten = await Promise.all(
+    (["monitor-added", "monitor-removed"] as const).map((name) =>
+      listen(name, (e: { payload: Monitor }) => callback(name, e.payload))
+    )
+  );
+  return () => unlisten.forEach((fn: () => void) => fn());
+}
+
 /**
  * List all connected MSI monitors
  */
@@ -24,26 +154,81 @@ export async function listMonitors(): Promise<Monitor[]> {
   return invoke("list_monitors");
 }
 
+/**
+ * Give a monitor a friendly name; an empty name clears it
+ */
+export async function renameMonitor(monitorId: string, name: string): Promise<void> {
+  return invoke("rename_monitor", { monitorId, name });
+}
+
 /**
  * Get settings for a specific monitor
- * Note: LED settings are not queryable from hardware, so defaults are added
+ * Served from the backend's short-lived cache unless forceRefresh is set
+ * Note: LED settings are not queryable from hardware; they come from the
+ * last config the backend applied, or defaults if there is none
  */
 export async function getMonitorSettings(
-  monitorId: string
+  monitorId: string,
+  forceRefresh = false
 ): Promise<MonitorSettings> {
   const settings = await invoke<Omit<MonitorSettings, "ledMode" | "ledColor" | "ledColor2">>(
     "get_monitor_settings",
-    { monitorId }
+    { monitorId, forceRefresh }
   );
-  // Add default LED values (not queryable from hardware)
+  const led = settings.lastKnownMysticLight?.config;
   return {
     ...settings,
-    ledMode: "off",
-    ledColor: "#ff0000",
-    ledColor2: "#0000ff",
+    ledMode: led?.mode ?? "off",
+    ledColor: led?.colors[0] ?? "#ff0000",
+    ledColor2: led?.colors[1] ?? "#0000ff",
   };
 }
 
+/**
+ * Get which settings a monitor supports
+ */
+export async function getMonitorCapabilities(monitorId: string): Promise<MonitorCapabilities> {
+  return invoke("get_monitor_capabilities", { monitorId });
+}
+
+/**
+ * Apply several settings in a single msigd call
+ */
+export async function applySettings(
+  monitorId: string,
+  settings: PartialMonitorSettings
+): Promise<FieldResult[]> {
+  return invoke("apply_settings", { monitorId, settings });
+}
+
+/**
+ * Get the registry describing every setting's kind and allowed values
+ */
+export async function getSettingSchema(): Promise<SettingDef[]> {
+  return invoke("get_setting_schema");
+}
+
+/**
+ * Set any single setting
+ */
+export async function setSetting(monitorId: string, setting: SettingValue): Promise<void> {
+  return invoke("set_setting", { monitorId, setting });
+}
+
+/**
+ * Move a numeric or RGB setting to `target` over `durationMs`
+ * Resolves with "cancelled" when a newer write to the setting takes over
+ */
+export async function transitionSetting(
+  monitorId: string,
+  setting: SettingField,
+  target: unknown,
+  durationMs: number,
+  easing: Easing = "ease_in_out"
+): Promise<TransitionOutcome> {
+  return invoke("transition_setting", { monitorId, setting, target, durationMs, easing });
+}
+
 /**
  * Set brightness (0-100)
  */
@@ -153,12 +338,19 @@ export async function setRefreshRateDisplay(
   return invoke("set_refresh_rate_display", { monitorId, enabled });
 }
 
+/**
+ * Get how many colors each Mystic Light mode takes
+ */
+export async function getMysticLightModes(): Promise<MysticLightModeInfo[]> {
+  return invoke("get_mystic_light_modes");
+}
+
 /**
  * Set Mystic Light LED configuration
  */
 export async function setMysticLight(
   monitorId: string,
-  config: string
+  config: MysticLightConfig
 ): Promise<void> {
   return invoke("set_mystic_light", { monitorId, config });
 }
@@ -390,3 +582,183 @@ export async function setRgbLed(
 ): Promise<void> {
   return invoke("set_rgb_led", { monitorId, enabled });
 }
+
+/**
+ * List all saved profiles
+ */
+export async function listProfiles(): Promise<Profile[]> {
+  return invoke("list_profiles");
+}
+
+/**
+ * Save a monitor's current settings as a named profile
+ *
+ * The backend adds the last Mystic Light config applied to the monitor.
+ */
+export async function saveProfileFromMonitor(
+  monitorId: string,
+  name: string
+): Promise<Profile> {
+  return invoke("save_profile_from_monitor", { monitorId, name });
+}
+
+/**
+ * Apply a saved profile, returning the outcome per field
+ */
+export async function applyProfile(
+  monitorId: string,
+  name: string
+): Promise<FieldResult[]> {
+  return invoke("apply_profile", { monitorId, name });
+}
+
+/**
+ * Delete a saved profile
+ */
+export async function deleteProfile(name: string): Promise<void> {
+  return invoke("delete_profile", { name });
+}
+
+/**
+ * Export a saved profile to a .toml or .json file
+ */
+export async function exportProfile(
+  name: string,
+  path: string,
+  monitorId: string | null
+): Promise<void> {
+  return invoke("export_profile", { name, path, monitorId });
+}
+
+/**
+ * Import a profile file, warning if it was made for another model
+ *
+ * Fails with `profile_exists` if a profile with the same name is saved,
+ * unless `overwrite` is set.
+ */
+export async function importProfile(
+  path: string,
+  monitorId: string | null,
+  overwrite = false
+): Promise<ImportResult> {
+  return invoke("import_profile", { path, monitorId, overwrite });
+}
+
+/**
+ * Whether settings are re-applied when a monitor reconnects
+ */
+export async function getRestoreOnReconnect(): Promise<boolean> {
+  return invoke("get_restore_on_reconnect");
+}
+
+/**
+ * Turn restore on reconnect on or off
+ */
+export async function setRestoreOnReconnect(enabled: boolean): Promise<void> {
+  return invoke("set_restore_on_reconnect", { enabled });
+}
+
+/**
+ * What was restored or failed on recent reconnects, newest first
+ */
+export async function getRestoreLog(): Promise<RestoreReport[]> {
+  return invoke("get_restore_log");
+}
+
+/**
+ * List all schedule rules
+ */
+export async function listScheduleRules(): Promise<ScheduleRule[]> {
+  return invoke("list_schedule_rules");
+}
+
+/**
+ * Save a schedule rule, replacing any rule with the same name
+ */
+export async function saveScheduleRule(rule: ScheduleRule): Promise<void> {
+  return invoke("save_schedule_rule", { rule });
+}
+
+/**
+ * Delete a schedule rule by name
+ */
+export async function deleteScheduleRule(name: string): Promise<void> {
+  return invoke("delete_schedule_rule", { name });
+}
+
+/**
+ * List all app profile rules
+ */
+export async function listAppRules(): Promise<AppRule[]> {
+  return invoke("list_app_rules");
+}
+
+/**
+ * Save an app profile rule, replacing any rule with the same name
+ */
+export async function saveAppRule(rule: AppRule): Promise<void> {
+  return invoke("save_app_rule", { rule });
+}
+
+/**
+ * Delete an app profile rule by name
+ */
+export async function deleteAppRule(name: string): Promise<void> {
+  return invoke("delete_app_rule", { name });
+}
+
+/**
+ * Get the circadian mode config
+ */
+export async function getCircadianConfig(): Promise<CircadianConfig> {
+  return invoke("get_circadian_config");
+}
+
+/**
+ * Replace the circadian mode config
+ */
+export async function setCircadianConfig(config: CircadianConfig): Promise<void> {
+  return invoke("set_circadian_config", { config });
+}
+
+/**
+ * Today's sunrise and sunset at a location, computed offline
+ */
+export async function getSunTimes(latitude: number, longitude: number): Promise<SunTimes> {
+  return invoke("get_sun_times", { latitude, longitude });
+}
+
+/**
+ * Get the ambient light auto-brightness config
+ */
+export async function getAmbientConfig(): Promise<AmbientConfig> {
+  return invoke("get_ambient_config");
+}
+
+/**
+ * Replace the ambient light auto-brightness config
+ */
+export async function setAmbientConfig(config: AmbientConfig): Promise<void> {
+  return invoke("set_ambient_config", { config });
+}
+
+/**
+ * List IIO light sensors with their current reading
+ */
+export async function listLightSensors(): Promise<LightSensor[]> {
+  return invoke("list_light_sensors");
+}
+
+/**
+ * Check msigd, hidraw permissions and udev rules
+ */
+export async function diagnose(): Promise<DiagnosticReport> {
+  return invoke("diagnose");
+}
+
+/**
+ * Install the udev rule for MSI monitors (prompts for authentication)
+ */
+export async function installUdevRule(): Promise<void> {
+  return invoke("install_udev_rule");
+}
@@ -1,7 +1,12 @@
 // Svelte 5 reactive state for monitors
 
-import type { Monitor, MonitorSettings } from "../types";
-import { listMonitors, getMonitorSettings } from "../api/monitor";
+import type { Monitor, MonitorCapabilities, MonitorSettings, SettingField } from "../types";
+import {
+  listMonitors,
+  getMonitorSettings,
+  getMonitorCapabilities,
+  renameMonitor,
+} from "../api/monitor";
 import {
   loadPersistedState,
   savePersistedState,
@@ -17,6 +22,7 @@ class MonitorState {
   monitors = $state<Monitor[]>([]);
   selectedId = $state<string | null>(null);
   settings = $state<MonitorSettings | null>(null);
+  capabilities = $state<MonitorCapabilities | null>(null);
   loading = $state(false);
   error = $state<string | null>(null);
   isFromCache = $state(false);
@@ -29,6 +35,28 @@ class MonitorState {
     return this.monitors.find((m) => m.id === this.selectedId);
   }
 
+  /**
+   * Whether the selected monitor supports a setting
+   * Everything is shown until capabilities have been loaded
+   */
+  supports(field: SettingField): boolean {
+    return !this.capabilities || this.capabilities.settings.includes(field);
+  }
+
+  /**
+   * Load capabilities so unsupported controls can be hidden
+   */
+  async loadCapabilities(monitorId: string) {
+    try {
+      const capabilities = await getMonitorCapabilities(monitorId);
+      if (this.selectedId === monitorId) {
+        this.capabilities = capabilities;
+      }
+    } catch (e) {
+      console.error("Failed to load capabilities:", e);
+    }
+  }
+
   /**
    * Initialize state from persisted storage
    */
@@ -66,17 +94,10 @@ class MonitorState {
           : false;
 
         if (persistedMonitorExists && this.selectedId) {
-          // If we already have cached settings (from initialize), keep them
-          // Only query hardware if no cached settings exist
-          if (!this.settings) {
-            const cached = await getCachedSettings(this.selectedId);
-            if (cached) {
-              this.settings = cached;
-              this.isFromCache = true;
-            } else {
-              await this.loadSettings(this.selectedId);
-            }
-          }
+          await this.loadCapabilities(this.selectedId);
+          // Cached settings from initialize stay on screen until the
+          // backend answers, usually from its own query cache
+          await this.loadSettings(this.selectedId);
         } else {
           // Fallback to first monitor if persisted one doesn't exist
           if (this.selectedId && !persistedMonitorExists) {
@@ -99,25 +120,24 @@ class MonitorState {
 
   async selectMonitor(id: string) {
     this.selectedId = id;
+    this.capabilities = null;
 
     // Persist selection immediately
     await savePersistedState({ selectedMonitorId: id });
 
-    // Try to load from cache first
+    await this.loadCapabilities(id);
+
+    // Show persisted settings instantly, then load the backend's view
     const cached = await getCachedSettings(id);
     if (cached) {
-      // Use cache as source of truth - don't query hardware
       this.settings = cached;
       this.isFromCache = true;
-      this.loading = false;
-    } else {
-      // No cache - must query hardware
-      await this.loadSettings(id);
     }
+    await this.loadSettings(id);
   }
 
   /**
-   * Load settings from hardware (only used when no cache exists)
+   * Load settings through the backend, which caches queries per monitor
    */
   async loadSettings(monitorId: string) {
     const requestId = ++this.loadRequestId;
@@ -126,10 +146,18 @@ class MonitorState {
 
     try {
       const settings = await getMonitorSettings(monitorId);
+      const cached = await getCachedSettings(monitorId);
 
       // Ignore stale requests (user switched monitors while loading)
       if (requestId !== this.loadRequestId) return;
 
+      // Keep persisted LED settings unless the backend remembers them
+      if (cached && !settings.lastKnownMysticLight) {
+        settings.ledMode = cached.ledMode;
+        settings.ledColor = cached.ledColor;
+        settings.ledColor2 = cached.ledColor2;
+      }
+
       this.settings = settings;
       this.isFromCache = false;
 
@@ -163,15 +191,17 @@ class MonitorState {
     const cachedLedColor2 = this.settings?.ledColor2 ?? "#0000ff";
 
     try {
-      const settings = await getMonitorSettings(this.selectedId);
+      const settings = await getMonitorSettings(this.selectedId, true);
 
       // Ignore stale requests
       if (requestId !== this.loadRequestId) return;
 
-      // Preserve LED settings from cache
-      settings.ledMode = cachedLedMode;
-      settings.ledColor = cachedLedColor;
-      settings.ledColor2 = cachedLedColor2;
+      // Preserve LED settings from cache unless the backend remembers them
+      if (!settings.lastKnownMysticLight) {
+        settings.ledMode = cachedLedMode;
+        settings.ledColor = cachedLedColor;
+        settings.ledColor2 = cachedLedColor2;
+      }
 
       this.settings = settings;
       this.isFromCache = false;
@@ -202,6 +232,14 @@ class MonitorState {
     }
   }
 
+  async renameMonitor(id: string, name: string) {
+    await renameMonitor(id, name);
+    const trimmed = name.trim();
+    this.monitors = this.monitors.map((m) =>
+      m.id === id ? { ...m, name: trimmed || null } : m
+    );
+  }
+
   clearError() {
     this.error = null;
   }
@@ -1,10 +1,19 @@
 // Type definitions for msigd-gui
 
 export interface Monitor {
+  /** Stable across reboots and replugging */
   id: string;
+  /** Current msigd index */
+  index: string;
+  /** Friendly name set by the user */
+  name: string | null;
   serial: string;
+  vendor: string;
   model: string;
   firmware: string;
+  devicePath: string | null;
+  /** Serial plus device path, unique even for identical monitors */
+  identity: string;
 }
 
 export interface MonitorSettings {
@@ -48,25 +57,275 @@ export interface MonitorSettings {
   ledMode: MysticLightMode;
   ledColor: string;  // hex #RRGGBB
   ledColor2: string; // hex #RRGGBB
+  // Settings the monitor reported on the last query
+  reported?: SettingField[];
+  // LED config the app last applied; LED state can't be read from the device
+  lastKnownMysticLight?: LastKnownMysticLight | null;
+}
+
+// Settings stored on the monitor (LED fields are frontend-only)
+export type SettingField = Exclude<
+  keyof MonitorSettings,
+  "ledMode" | "ledColor" | "ledColor2" | "reported" | "lastKnownMysticLight"
+>;
+
+// Partial settings for batch writes (LED fields are not part of a batch)
+export type PartialMonitorSettings = Partial<Pick<MonitorSettings, SettingField>>;
+
+// Error returned by every command
+export type ErrorCode =
+  | "not_installed"
+  | "permission_denied"
+  | "execution_failed"
+  | "timeout"
+  | "command_failed"
+  | "parse_error"
+  | "invalid_value"
+  | "monitor_not_found"
+  | "unsupported_setting"
+  | "shell_error"
+  | "profile_not_found"
+  | "profile_exists"
+  | "rule_not_found"
+  | "storage_error"
+  | "sensor_error"
+  | "verify_failed";
+
+export interface CommandErrorBody {
+  code: ErrorCode;
+  message: string;
+  monitorId?: string;
+  setting?: string;
+  stderr?: string;
+  exitStatus?: number;
+  // Written and read-back values of a verify_failed error
+  expected?: string;
+  actual?: string;
+}
+
+export interface FieldResult {
+  field: string;
+  success: boolean;
+  error: string | null;
+  errorCode: ErrorCode | null;
+}
+
+export interface RestoreReport {
+  monitorId: string;
+  model: string;
+  /** Seconds since the Unix epoch */
+  timestamp: number;
+  results: FieldResult[];
+}
+
+// Daily rule run by the backend scheduler
+export type ScheduleAction =
+  | { type: "settings"; settings: PartialMonitorSettings }
+  | { type: "profile"; name: string };
+
+export interface ScheduleRule {
+  name: string;
+  monitorId: string;
+  /** Local time of day, "HH:MM" */
+  time: string;
+  action: ScheduleAction;
+  /** Seconds over which numeric settings move to their target */
+  rampSecs: number;
+  enabled: boolean;
+}
+
+export interface ScheduleRun {
+  rule: string;
+  monitorId: string;
+  /** Seconds since the Unix epoch */
+  timestamp: number;
+  results: FieldResult[];
+}
+
+// Profile applied while a matching application runs
+export type MatchOn = "name" | "cmdline";
+
+export interface AppRule {
+  name: string;
+  /** Process name, or command line substring with matchOn "cmdline" */
+  process: string;
+  matchOn: MatchOn;
+  profile: string;
+  monitorId: string;
+  /** Higher wins when several rules match the same monitor */
+  priority: number;
+  enabled: boolean;
+}
+
+export interface AppSwitch {
+  monitorId: string;
+  /** Rule now in effect, null after restoring the previous settings */
+  rule: string | null;
+  profile: string | null;
+  /** Seconds since the Unix epoch */
+  timestamp: number;
+  results: FieldResult[];
+}
+
+// Circadian mode: brightness and color follow sunrise and sunset
+export interface CircadianTarget {
+  brightness: number;
+  colorRgb: MonitorSettings["colorRgb"];
+}
+
+export interface CircadianConfig {
+  enabled: boolean;
+  /** Degrees, positive north */
+  latitude: number;
+  /** Degrees, positive east */
+  longitude: number;
+  monitorIds: string[];
+  day: CircadianTarget;
+  night: CircadianTarget;
+  /** Seconds between updates */
+  stepSecs: number;
+}
+
+export interface SunTimes {
+  /** Seconds since the Unix epoch, null during polar day or night */
+  sunrise: number | null;
+  sunset: number | null;
+  polarDay: boolean;
+}
+
+// Auto-brightness from an ambient light sensor
+export interface CurvePoint {
+  lux: number;
+  brightness: number;
+}
+
+export interface AmbientConfig {
+  enabled: boolean;
+  /** IIO device such as "iio:device0", null for the first light sensor */
+  sensor: string | null;
+  monitorIds: string[];
+  /** Points sorted by lux; brightness is interpolated between them */
+  curve: CurvePoint[];
+  /** Minimum brightness change before a new value is written */
+  hysteresis: number;
+  /** Seconds between sensor reads */
+  pollSecs: number;
+}
+
+export interface LightSensor {
+  device: string;
+  name: string;
+  /** Current reading, null when the sensor can't be read */
+  lux: number | null;
+}
+
+// Smooth transitions of numeric and RGB settings
+export type Easing = "linear" | "ease_in" | "ease_out" | "ease_in_out";
+
+export type TransitionOutcome = "completed" | "cancelled";
+
+export interface TransitionProgress {
+  monitorId: string;
+  setting: SettingField;
+  /** Value just written, e.g. a number or { r, g, b } */
+  value: unknown;
+  /** Time-based progress from 0 to 1 */
+  progress: number;
+}
+
+// Setting registry returned by get_setting_schema
+export type SettingKind =
+  | { type: "numeric"; min: number; max: number }
+  | { type: "toggle" }
+  | { type: "choice"; values: string[] }
+  | { type: "rgb"; max: number };
+
+// Argument of set_setting, e.g. { setting: "brightness", value: 40 }
+export type SettingValue = {
+  [K in SettingField]: { setting: K; value: MonitorSettings[K] };
+}[SettingField];
+
+export interface SettingDef {
+  field: SettingField;
+  flag: string;
+  label: string;
+  kind: SettingKind;
+}
+
+export interface MonitorCapabilities {
+  settings: SettingField[];
+}
+
+// Result of the diagnose command
+export interface DeviceReport {
+  monitorId: string;
+  path: string;
+  exists: boolean;
+  owner: string | null;
+  group: string | null;
+  mode: string | null;
+  acl: string[];
+  inGroup: boolean;
+  accessible: boolean;
+  error: string | null;
+}
+
+export interface DiagnosticReport {
+  msigdInstalled: boolean;
+  uid: number | null;
+  groups: string[];
+  devices: DeviceReport[];
+  udevRule: string | null;
+  canInstallRule: boolean;
+  suggestions: string[];
+}
+
+export interface Profile {
+  name: string;
+  settings: PartialMonitorSettings;
+  mysticLight: MysticLightConfig | null;
+}
+
+export interface ImportResult {
+  profile: Profile;
+  warnings: string[];
 }
 
 export interface MysticLightConfig {
+  /** LED group, "0" or "1" */
   ledGroup: string;
   mode: MysticLightMode;
+  /** Colors as #rrggbb; the count depends on the mode */
   colors: string[];
 }
 
-// msigd supported modes: off, static, breathing, blinking, flashing, blinds, meteor, rainbow, random
+export interface LastKnownMysticLight {
+  config: MysticLightConfig;
+  /** Seconds since the Unix epoch */
+  appliedAt: number;
+}
+
+// Validated and converted to msigd's mystic syntax by the backend
 export type MysticLightMode =
   | "off"
   | "static"
   | "breathing"
   | "blinking"
   | "flashing"
+  | "double_flashing"
   | "blinds"
+  | "lightning"
   | "meteor"
   | "rainbow"
-  | "random";
+  | "random"
+  | "rainbow_gradient";
+
+// Returned by get_mystic_light_modes
+export interface MysticLightModeInfo {
+  mode: MysticLightMode;
+  minColors: number;
+  maxColors: number;
+}
 
 // Phase 2: MAG Core types
 export type NightVision = "off" | "normal" | "strong" | "strongest" | "ai";